@@ -0,0 +1,477 @@
+//! Interactive audit of a chain while it runs, with a deadline for every checkpoint.
+//!
+//! A [`Tag`] only shows after the fact that the chain was computed. In an audit the verifier sends the initial
+//! challenge, and the prover reports the record `(i, c_i, v_i)` of every `every`-th round as soon as it has it and the
+//! [`Response`] at the end. Round `i` cannot be answered before `i` delays have run, so its deadline is `i` round times
+//! after the challenge plus some slack; a checkpoint that misses it shows that the data was not at hand when the
+//! chain reached that point. The chain runs a delay after its last round `k` too, so the response is due like a round
+//! `k + 1`.
+//!
+//! Both sides are plain state machines without I/O: the caller moves [`Message`]s between them and passes the current
+//! time in, and the verifier reports what it found as [`Finding`]s.
+
+use std::{
+    collections::BTreeSet,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    error::{PostError, Result},
+    post::{Response, Tag},
+    transcript::Transcript,
+    verify::{verify, Rejection, Verdict},
+};
+
+/// What the prover and the verifier send each other.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Message {
+    /// Verifier to prover: the initial challenge of the chain.
+    Challenge { c: Vec<u8> },
+    /// Prover to verifier: the record of a checkpoint round.
+    Checkpoint {
+        round: usize,
+        c: Vec<u8>,
+        v: Vec<u8>,
+    },
+    /// Prover to verifier: the response over the whole chain.
+    Response(Response),
+}
+
+/// Which rounds are checkpoints and when they are due.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Schedule {
+    every: usize,
+    round_time: Duration,
+    slack: Duration,
+}
+
+impl Schedule {
+    /// Every `every`-th round from round `0` on is a checkpoint. `round_time` is how long an honest prover takes for a
+    /// delay, e.g. from [`Calibration::round_time`](crate::calibrate::Calibration::round_time), and `slack` covers the
+    /// round function and the network.
+    pub fn new(every: usize, round_time: Duration, slack: Duration) -> Result<Self> {
+        if every == 0 {
+            return Err(PostError::InvalidParameters(
+                "checkpoints must be at least one round apart".into(),
+            ));
+        }
+        Ok(Self {
+            every,
+            round_time,
+            slack,
+        })
+    }
+
+    pub fn every(&self) -> usize {
+        self.every
+    }
+
+    pub fn is_checkpoint(&self, round: usize) -> bool {
+        round.is_multiple_of(self.every)
+    }
+
+    /// The checkpoint rounds of a chain of `k` delays.
+    pub fn checkpoints(&self, k: usize) -> impl Iterator<Item = usize> {
+        (0..=k).step_by(self.every)
+    }
+
+    /// How long after the challenge round `round` is due.
+    pub fn deadline(&self, round: usize) -> Duration {
+        self.round_time
+            .saturating_mul(round.try_into().unwrap_or(u32::MAX))
+            .saturating_add(self.slack)
+    }
+
+    /// How long after the challenge the response of a chain of `k` delays is due, i.e. after its `k + 1` delays.
+    pub fn response_deadline(&self, k: usize) -> Duration {
+        self.deadline(k.saturating_add(1))
+    }
+}
+
+/// Something the verifier flagged during an audit.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Finding {
+    /// The checkpoint of the given round arrived after its deadline.
+    Late { round: usize, by: Duration },
+    /// The final response arrived after its deadline.
+    LateResponse { by: Duration },
+    /// The deadline of the checkpoint passed without it arriving; it may still arrive late.
+    Missing { round: usize },
+    /// The checkpoint does not match the verifier's record of the round.
+    Wrong { round: usize, rejection: Rejection },
+    /// A checkpoint for a round that is not one, or that was already received.
+    Unexpected { round: usize },
+    /// The prover sent something that the protocol does not allow at all, which ends the audit.
+    Violation { reason: String },
+}
+
+/// The verifier's side of an audit, checking the prover against its own transcript of the chain.
+pub struct AuditVerifier {
+    expected: Transcript,
+    tag: Tag,
+    schedule: Schedule,
+    start: Option<Instant>,
+    // Checkpoints not received yet, and those of them already flagged as missing.
+    pending: BTreeSet<usize>,
+    missing: BTreeSet<usize>,
+    findings: Vec<Finding>,
+    verdict: Option<Verdict>,
+}
+
+impl AuditVerifier {
+    /// `expected` is the verifier's transcript, computed with the trapdoor by
+    /// [`Store::run_with_transcript`](crate::Store::run_with_transcript) for the challenge of this audit.
+    pub fn new(expected: Transcript, schedule: Schedule) -> Result<Self> {
+        if expected.rounds.is_empty() {
+            return Err(PostError::InvalidParameters(
+                "cannot audit an empty chain".into(),
+            ));
+        }
+        let k = expected.rounds.len() - 1;
+        Ok(Self {
+            tag: expected.tag()?,
+            pending: schedule.checkpoints(k).collect(),
+            missing: BTreeSet::new(),
+            expected,
+            schedule,
+            start: None,
+            findings: vec![],
+            verdict: None,
+        })
+    }
+
+    fn k(&self) -> usize {
+        self.expected.rounds.len() - 1
+    }
+
+    /// Starts the clock and returns the challenge to send to the prover.
+    pub fn start(&mut self, now: Instant) -> Result<Message> {
+        if self.start.is_some() {
+            return Err(PostError::Protocol("audit already started".into()));
+        }
+        self.start = Some(now);
+        Ok(Message::Challenge {
+            c: self.expected.rounds[0].c.clone(),
+        })
+    }
+
+    /// Handles a message from the prover received at `now`, returning what it newly flagged.
+    pub fn handle(&mut self, message: Message, now: Instant) -> Result<Vec<Finding>> {
+        let Some(start) = self.start else {
+            return Err(PostError::Protocol("audit not started".into()));
+        };
+        if self.verdict.is_some() {
+            return Err(PostError::Protocol("audit already finished".into()));
+        }
+        let mut findings = self.poll(now);
+        let mut new = vec![];
+        match message {
+            Message::Challenge { .. } => {
+                return Err(PostError::Protocol(
+                    "the prover cannot send a challenge".into(),
+                ))
+            }
+            Message::Checkpoint { round, c, v } => {
+                if !self.pending.remove(&round) {
+                    new.push(Finding::Unexpected { round });
+                } else {
+                    self.missing.remove(&round);
+                    new.extend(
+                        lateness(start, self.schedule.deadline(round), now)
+                            .map(|by| Finding::Late { round, by }),
+                    );
+                    let expected = &self.expected.rounds[round];
+                    let rejection = if expected.c != c {
+                        Some(Rejection::ChallengeMismatch)
+                    } else if expected.v != v {
+                        Some(Rejection::RoundResponse { round })
+                    } else {
+                        None
+                    };
+                    if let Some(rejection) = rejection {
+                        new.push(Finding::Wrong { round, rejection });
+                    }
+                }
+            }
+            Message::Response(response) => {
+                let due = self.schedule.response_deadline(self.k());
+                new.extend(lateness(start, due, now).map(|by| Finding::LateResponse { by }));
+                for round in std::mem::take(&mut self.pending) {
+                    if !self.missing.contains(&round) {
+                        new.push(Finding::Missing { round });
+                    }
+                }
+                self.verdict = Some(verify(&self.tag, &response));
+            }
+        }
+        self.findings.extend(new.iter().cloned());
+        findings.extend(new);
+        Ok(findings)
+    }
+
+    /// Flags the checkpoints whose deadline passed by `now`; call it at [`AuditVerifier::next_deadline`].
+    pub fn poll(&mut self, now: Instant) -> Vec<Finding> {
+        let Some(start) = self.start else {
+            return vec![];
+        };
+        let overdue = self
+            .pending
+            .iter()
+            .filter(|&&round| !self.missing.contains(&round))
+            .filter(|&&round| now > start + self.schedule.deadline(round))
+            .copied()
+            .collect::<Vec<_>>();
+        self.missing.extend(&overdue);
+        let findings = overdue
+            .into_iter()
+            .map(|round| Finding::Missing { round })
+            .collect::<Vec<_>>();
+        self.findings.extend(findings.iter().cloned());
+        findings
+    }
+
+    /// Ends an audit that will get no response, e.g. because the prover hung up, flagging every checkpoint still
+    /// outstanding as missing.
+    pub fn abandon(&mut self) -> Vec<Finding> {
+        let findings = std::mem::take(&mut self.pending)
+            .into_iter()
+            .filter(|round| !self.missing.contains(round))
+            .map(|round| Finding::Missing { round })
+            .collect::<Vec<_>>();
+        self.findings.extend(findings.iter().cloned());
+        findings
+    }
+
+    /// When the next checkpoint or the final response is due, if the audit is running.
+    pub fn next_deadline(&self) -> Option<Instant> {
+        let start = self.start?;
+        if self.verdict.is_some() {
+            return None;
+        }
+        let deadline = self
+            .pending
+            .iter()
+            .find(|round| !self.missing.contains(round))
+            .map_or(self.schedule.response_deadline(self.k()), |&round| {
+                self.schedule.deadline(round)
+            });
+        Some(start + deadline)
+    }
+
+    /// Everything flagged so far.
+    pub fn findings(&self) -> &[Finding] {
+        &self.findings
+    }
+
+    /// The comparison of the final response with the tag, once it arrived.
+    pub fn verdict(&self) -> Option<Verdict> {
+        self.verdict
+    }
+
+    /// Whether the response arrived, all checkpoints on time and right, and the response matches the tag.
+    pub fn passed(&self) -> bool {
+        self.findings.is_empty() && self.verdict.is_some_and(|v| v.is_accepted())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ProverState {
+    AwaitingChallenge,
+    Running,
+    Finished,
+}
+
+/// The prover's side of an audit, deciding which rounds to report.
+///
+/// Feed it every round as soon as it is answered, e.g. from
+/// [`Prove::run_with_rounds`](crate::Prove::run_with_rounds).
+pub struct AuditProver {
+    schedule: Schedule,
+    state: ProverState,
+}
+
+impl AuditProver {
+    pub fn new(schedule: Schedule) -> Self {
+        Self {
+            schedule,
+            state: ProverState::AwaitingChallenge,
+        }
+    }
+
+    /// Accepts the verifier's challenge and returns it for the chain to start from.
+    pub fn handle(&mut self, message: Message) -> Result<Vec<u8>> {
+        match (self.state, message) {
+            (ProverState::AwaitingChallenge, Message::Challenge { c }) => {
+                self.state = ProverState::Running;
+                Ok(c)
+            }
+            _ => Err(PostError::Protocol("expected a challenge".into())),
+        }
+    }
+
+    /// The checkpoint to send for round `round`, if it is one.
+    pub fn on_round(&mut self, round: usize, c: &[u8], v: &[u8]) -> Result<Option<Message>> {
+        if self.state != ProverState::Running {
+            return Err(PostError::Protocol("no chain is running".into()));
+        }
+        Ok(self
+            .schedule
+            .is_checkpoint(round)
+            .then(|| Message::Checkpoint {
+                round,
+                c: c.to_vec(),
+                v: v.to_vec(),
+            }))
+    }
+
+    /// The final message once the chain is done.
+    pub fn finish(&mut self, response: Response) -> Result<Message> {
+        if self.state != ProverState::Running {
+            return Err(PostError::Protocol("no chain is running".into()));
+        }
+        self.state = ProverState::Finished;
+        Ok(Message::Response(response))
+    }
+}
+
+// How long after `deadline` something that arrived at `now` was, if it was late at all.
+fn lateness(start: Instant, deadline: Duration, now: Instant) -> Option<Duration> {
+    let due = start + deadline;
+    (now > due).then(|| now - due)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        delay::DelayFunction,
+        post::{Prove, Store},
+        round::FileMac,
+    };
+
+    const K: usize = 4;
+    const ROUND_TIME: Duration = Duration::from_secs(60);
+
+    // The audit only sees when messages arrive, so the delay itself can be free.
+    struct Free;
+
+    impl DelayFunction for Free {
+        fn eval(&self, x: &[u8]) -> Result<Vec<u8>> {
+            Ok(x.to_vec())
+        }
+
+        fn eval_trapdoor(&self, x: &[u8]) -> Result<Vec<u8>> {
+            Ok(x.to_vec())
+        }
+    }
+
+    fn schedule() -> Schedule {
+        Schedule::new(2, ROUND_TIME, ROUND_TIME / 5).unwrap()
+    }
+
+    // Audits a prover holding `data` against the verifier's record of `stored`. Whatever the prover sends after `i`
+    // delays arrives `arrival(i)` after the challenge.
+    fn audit(stored: &[u8], data: &[u8], arrival: impl Fn(u32) -> Duration) -> AuditVerifier {
+        let (_, expected) = Store::new(Free, K)
+            .run_with_transcript(b"challenge", &FileMac(stored), false)
+            .unwrap();
+        let mut verifier = AuditVerifier::new(expected, schedule()).unwrap();
+        let mut prover = AuditProver::new(schedule());
+        let start = Instant::now();
+        let c = prover.handle(verifier.start(start).unwrap()).unwrap();
+        let mut sent = vec![];
+        let response = Prove::new(Free, K)
+            .run_with_rounds(&c, &FileMac(data), |i, c, v| {
+                sent.extend(prover.on_round(i, c, v)?.map(|m| (i as u32, m)));
+                Ok(())
+            })
+            .unwrap();
+        sent.push((K as u32 + 1, prover.finish(response).unwrap()));
+        for (delays, message) in sent {
+            verifier.handle(message, start + arrival(delays)).unwrap();
+        }
+        verifier
+    }
+
+    #[test]
+    fn honest_prover_passes() {
+        let verifier = audit(b"data", b"data", |delays| {
+            ROUND_TIME * delays + ROUND_TIME / 10
+        });
+        assert_eq!(verifier.findings(), []);
+        assert!(verifier.passed());
+        assert_eq!(verifier.next_deadline(), None);
+    }
+
+    #[test]
+    fn slow_prover_is_late() {
+        let verifier = audit(b"data", b"data", |delays| ROUND_TIME * delays * 3 / 2);
+        assert_eq!(verifier.verdict(), Some(Verdict::Accepted));
+        assert_eq!(
+            verifier.findings(),
+            [
+                Finding::Missing { round: 2 },
+                Finding::Late {
+                    round: 2,
+                    by: ROUND_TIME * 3 - ROUND_TIME * 2 - ROUND_TIME / 5
+                },
+                Finding::Missing { round: 4 },
+                Finding::Late {
+                    round: 4,
+                    by: ROUND_TIME * 6 - ROUND_TIME * 4 - ROUND_TIME / 5
+                },
+                Finding::LateResponse {
+                    by: ROUND_TIME * 15 / 2 - ROUND_TIME * 5 - ROUND_TIME / 5
+                },
+            ]
+        );
+        assert!(!verifier.passed());
+    }
+
+    #[test]
+    fn wrong_data_is_flagged() {
+        let verifier = audit(b"data", b"other data", |delays| ROUND_TIME * delays);
+        assert_eq!(
+            verifier.findings()[0],
+            Finding::Wrong {
+                round: 0,
+                rejection: Rejection::RoundResponse { round: 0 }
+            }
+        );
+        assert_eq!(
+            verifier.verdict(),
+            Some(Verdict::Rejected(Rejection::ChallengeMismatch))
+        );
+    }
+
+    #[test]
+    fn missing_checkpoints() {
+        let (_, expected) = Store::new(Free, K)
+            .run_with_transcript(b"challenge", &FileMac(b"data"), false)
+            .unwrap();
+        let mut verifier = AuditVerifier::new(expected, schedule()).unwrap();
+        let start = Instant::now();
+        verifier.start(start).unwrap();
+        assert_eq!(verifier.next_deadline(), Some(start + ROUND_TIME / 5));
+        assert_eq!(
+            verifier.poll(start + ROUND_TIME * 3),
+            [0, 2].map(|round| Finding::Missing { round })
+        );
+        assert_eq!(verifier.next_deadline(), Some(start + ROUND_TIME * 21 / 5));
+        assert_eq!(verifier.abandon(), [Finding::Missing { round: 4 }]);
+        assert!(!verifier.passed());
+    }
+
+    #[test]
+    fn prover_follows_the_protocol() {
+        let mut prover = AuditProver::new(schedule());
+        assert!(prover.on_round(0, b"c", b"v").is_err());
+        let c = prover
+            .handle(Message::Challenge { c: b"c".to_vec() })
+            .unwrap();
+        assert_eq!(c, b"c");
+        assert!(prover.handle(Message::Challenge { c }).is_err());
+        assert_eq!(prover.on_round(1, b"c", b"v").unwrap(), None);
+        assert!(prover.on_round(2, b"c", b"v").unwrap().is_some());
+    }
+}
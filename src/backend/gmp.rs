@@ -0,0 +1,88 @@
+use rand::{rngs::OsRng, RngCore};
+use rug::{
+    integer::{IsPrime, Order},
+    ops::RemRounding,
+    Integer,
+};
+
+use crate::{
+    backend::BigIntBackend,
+    error::{PostError, Result},
+};
+
+pub struct Gmp;
+
+impl BigIntBackend for Gmp {
+    type Int = Integer;
+
+    fn from_u64(a: u64) -> Result<Integer> {
+        Ok(Integer::from(a))
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Integer> {
+        Ok(Integer::from_digits(bytes, Order::Msf))
+    }
+
+    fn to_bytes(a: &Integer) -> Result<Vec<u8>> {
+        Ok(a.to_digits(Order::Msf))
+    }
+
+    fn generate_prime(bits: u32) -> Result<Integer> {
+        loop {
+            let mut bytes = vec![0; (bits as usize).div_ceil(8)];
+            OsRng.fill_bytes(&mut bytes);
+            let mut p = Integer::from_digits(&bytes, Order::Msf);
+            p.keep_bits_mut(bits);
+            p.set_bit(bits - 1, true);
+            p.next_prime_mut();
+            if p.significant_bits() == bits {
+                return Ok(p);
+            }
+        }
+    }
+
+    fn is_prime(a: &Integer) -> Result<bool> {
+        Ok(a.is_probably_prime(40) != IsPrime::No)
+    }
+
+    fn add(a: &Integer, b: &Integer) -> Result<Integer> {
+        Ok(Integer::from(a + b))
+    }
+
+    fn sub(a: &Integer, b: &Integer) -> Result<Integer> {
+        Ok(Integer::from(a - b))
+    }
+
+    fn mul(a: &Integer, b: &Integer) -> Result<Integer> {
+        Ok(Integer::from(a * b))
+    }
+
+    fn rem(a: &Integer, m: &Integer) -> Result<Integer> {
+        Ok(a.clone().rem_euc(m))
+    }
+
+    fn mod_mul(a: &Integer, b: &Integer, m: &Integer) -> Result<Integer> {
+        Ok(Integer::from(a * b).rem_euc(m))
+    }
+
+    fn mod_exp(a: &Integer, e: &Integer, m: &Integer) -> Result<Integer> {
+        a.pow_mod_ref(e, m)
+            .map(Integer::from)
+            .ok_or_else(|| PostError::InvalidParameters("invalid modular exponentiation".into()))
+    }
+
+    fn mod_inverse(a: &Integer, m: &Integer) -> Result<Integer> {
+        a.invert_ref(m)
+            .map(Integer::from)
+            .ok_or_else(|| PostError::InvalidParameters("element is not invertible".into()))
+    }
+
+    fn square_repeated(x: &Integer, times: u64, n: &Integer) -> Result<Integer> {
+        let mut g = x.clone();
+        for _ in 0..times {
+            g.square_mut();
+            g %= n;
+        }
+        Ok(g)
+    }
+}
@@ -0,0 +1,135 @@
+//! Big-integer arithmetic behind the RSA delay function.
+//!
+//! [`OpenSsl`], [`Gmp`] (via `rug`) and the pure-Rust [`NumBigint`] are enabled by the `openssl`, `gmp` and
+//! `num-bigint` features, and at least one is required. The backend covers modulus generation,
+//! [`DelayFunction::eval`](crate::DelayFunction::eval) and the trapdoor evaluation, so that squaring throughput can be
+//! compared across libraries.
+//!
+//! Without the default `openssl` feature nothing links libssl, but the delay proofs, the class group, Shacham-Waters PoR
+//! and the Montgomery squaring of [`OpenSsl`] are left out.
+
+#[cfg(feature = "gmp")]
+mod gmp;
+#[cfg(feature = "num-bigint")]
+mod num;
+#[cfg(feature = "openssl")]
+mod openssl;
+
+#[cfg(feature = "gmp")]
+pub use self::gmp::Gmp;
+#[cfg(feature = "num-bigint")]
+pub use self::num::NumBigint;
+#[cfg(feature = "openssl")]
+pub use self::openssl::OpenSsl;
+
+#[cfg(not(any(feature = "openssl", feature = "gmp", feature = "num-bigint")))]
+compile_error!("enable at least one of the openssl, gmp and num-bigint features");
+
+/// The backend of [`RsaSquaring`](crate::RsaSquaring), [`ModulusGenerator`](crate::ModulusGenerator) and
+/// [`Trapdoor`](crate::Trapdoor) unless another is named: the first enabled of OpenSSL, GMP and `num-bigint`.
+#[cfg(feature = "openssl")]
+pub type DefaultBackend = OpenSsl;
+#[cfg(all(not(feature = "openssl"), feature = "gmp"))]
+pub type DefaultBackend = Gmp;
+#[cfg(all(not(feature = "openssl"), not(feature = "gmp"), feature = "num-bigint"))]
+pub type DefaultBackend = NumBigint;
+
+use crate::error::Result;
+
+/// Non-negative integers and the operations the RSA squaring chain needs.
+pub trait BigIntBackend {
+    type Int;
+
+    fn from_u64(a: u64) -> Result<Self::Int>;
+
+    /// Parses a big-endian magnitude.
+    fn from_bytes(bytes: &[u8]) -> Result<Self::Int>;
+
+    /// Big-endian magnitude without leading zeros.
+    fn to_bytes(a: &Self::Int) -> Result<Vec<u8>>;
+
+    /// A random prime of exactly `bits` bits.
+    fn generate_prime(bits: u32) -> Result<Self::Int>;
+
+    /// A random prime `p = 2p' + 1` of exactly `bits` bits with `p'` prime.
+    fn generate_safe_prime(bits: u32) -> Result<Self::Int> {
+        let one = Self::from_u64(1)?;
+        loop {
+            let p1 = Self::generate_prime(bits - 1)?;
+            let p = Self::add(&Self::add(&p1, &p1)?, &one)?;
+            if Self::is_prime(&p)? {
+                return Ok(p);
+            }
+        }
+    }
+
+    /// Probabilistic primality test with a negligible error probability.
+    fn is_prime(a: &Self::Int) -> Result<bool>;
+
+    fn add(a: &Self::Int, b: &Self::Int) -> Result<Self::Int>;
+
+    /// `a - b`, with `a >= b`.
+    fn sub(a: &Self::Int, b: &Self::Int) -> Result<Self::Int>;
+
+    fn mul(a: &Self::Int, b: &Self::Int) -> Result<Self::Int>;
+
+    fn rem(a: &Self::Int, m: &Self::Int) -> Result<Self::Int>;
+
+    fn mod_mul(a: &Self::Int, b: &Self::Int, m: &Self::Int) -> Result<Self::Int>;
+
+    fn mod_exp(a: &Self::Int, e: &Self::Int, m: &Self::Int) -> Result<Self::Int>;
+
+    fn mod_inverse(a: &Self::Int, m: &Self::Int) -> Result<Self::Int>;
+
+    /// Squares the reduced `x` `times` times modulo the odd `n`; this is the sequential hot loop.
+    fn square_repeated(x: &Self::Int, times: u64, n: &Self::Int) -> Result<Self::Int>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rsa::{eval_trap, eval_with, CrtTrapdoor};
+
+    // Checks the chain and its trapdoor evaluation in `B` against `Reference` on the same modulus.
+    fn agrees_with<B: BigIntBackend, Reference: BigIntBackend>() {
+        let p = Reference::generate_prime(256).unwrap();
+        let q = Reference::generate_prime(256).unwrap();
+        let n = Reference::mul(&p, &q).unwrap();
+        let convert = |a| B::from_bytes(&Reference::to_bytes(a).unwrap()).unwrap();
+        for squarings in [1, 2, 1000] {
+            let trapdoor = CrtTrapdoor::<B>::new(convert(&p), convert(&q), squarings).unwrap();
+            for x in [&b"x"[..], &[0xff; 80]] {
+                let y = eval_with::<Reference>(x, &n, squarings).unwrap();
+                assert_eq!(eval_with::<B>(x, &convert(&n), squarings).unwrap(), y);
+                assert_eq!(eval_trap(x, &trapdoor).unwrap(), y);
+            }
+        }
+        let prime = B::to_bytes(&B::generate_prime(128).unwrap()).unwrap();
+        assert!(Reference::is_prime(&Reference::from_bytes(&prime).unwrap()).unwrap());
+        assert_eq!(prime.len(), 16);
+    }
+
+    // The reference is OpenSSL when it is built, and otherwise the backend checks itself.
+    #[cfg(feature = "openssl")]
+    type Reference = OpenSsl;
+    #[cfg(not(feature = "openssl"))]
+    type Reference = DefaultBackend;
+
+    #[cfg(feature = "openssl")]
+    #[test]
+    fn openssl() {
+        agrees_with::<OpenSsl, Reference>();
+    }
+
+    #[cfg(feature = "num-bigint")]
+    #[test]
+    fn num_bigint() {
+        agrees_with::<NumBigint, Reference>();
+    }
+
+    #[cfg(feature = "gmp")]
+    #[test]
+    fn gmp() {
+        agrees_with::<Gmp, Reference>();
+    }
+}
@@ -0,0 +1,126 @@
+use num_bigint::{BigUint, RandBigInt};
+use num_traits::{One, Zero};
+use rand::rngs::OsRng;
+
+use crate::{
+    backend::BigIntBackend,
+    error::{PostError, Result},
+};
+
+/// Pure-Rust arithmetic with `num-bigint`, for hosts without a C toolchain for GMP.
+pub struct NumBigint;
+
+const SMALL_PRIMES: [u32; 24] = [
+    3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97,
+];
+
+// Trial division, then 40 rounds of Miller-Rabin with random bases.
+fn is_prime(n: &BigUint) -> bool {
+    for p in SMALL_PRIMES {
+        if (n % p).is_zero() {
+            return *n == BigUint::from(p);
+        }
+    }
+    let one = BigUint::one();
+    let n1 = n - &one;
+    let s = n1.trailing_zeros().unwrap_or(0);
+    let d = &n1 >> s;
+    let two = BigUint::from(2u32);
+    'witness: for _ in 0..40 {
+        let a = OsRng.gen_biguint_range(&two, &n1);
+        let mut x = a.modpow(&d, n);
+        if x == one || x == n1 {
+            continue;
+        }
+        for _ in 1..s {
+            x = x.modpow(&two, n);
+            if x == n1 {
+                continue 'witness;
+            }
+        }
+        return false;
+    }
+    true
+}
+
+impl BigIntBackend for NumBigint {
+    type Int = BigUint;
+
+    fn from_u64(a: u64) -> Result<BigUint> {
+        Ok(BigUint::from(a))
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<BigUint> {
+        Ok(BigUint::from_bytes_be(bytes))
+    }
+
+    fn to_bytes(a: &BigUint) -> Result<Vec<u8>> {
+        if a.is_zero() {
+            return Ok(vec![]);
+        }
+        Ok(a.to_bytes_be())
+    }
+
+    fn generate_prime(bits: u32) -> Result<BigUint> {
+        if bits < 8 {
+            return Err(PostError::InvalidParameters(format!(
+                "primes of {} bits are not supported",
+                bits
+            )));
+        }
+        loop {
+            let mut p = OsRng.gen_biguint(bits as u64);
+            p.set_bit(bits as u64 - 1, true);
+            p.set_bit(0, true);
+            if is_prime(&p) {
+                return Ok(p);
+            }
+        }
+    }
+
+    fn is_prime(a: &BigUint) -> Result<bool> {
+        if a.bits() < 8 {
+            return Ok(
+                *a == BigUint::from(2u32) || SMALL_PRIMES.iter().any(|&p| *a == BigUint::from(p))
+            );
+        }
+        Ok(a.bit(0) && is_prime(a))
+    }
+
+    fn add(a: &BigUint, b: &BigUint) -> Result<BigUint> {
+        Ok(a + b)
+    }
+
+    fn sub(a: &BigUint, b: &BigUint) -> Result<BigUint> {
+        Ok(a - b)
+    }
+
+    fn mul(a: &BigUint, b: &BigUint) -> Result<BigUint> {
+        Ok(a * b)
+    }
+
+    fn rem(a: &BigUint, m: &BigUint) -> Result<BigUint> {
+        Ok(a % m)
+    }
+
+    fn mod_mul(a: &BigUint, b: &BigUint, m: &BigUint) -> Result<BigUint> {
+        Ok(a * b % m)
+    }
+
+    fn mod_exp(a: &BigUint, e: &BigUint, m: &BigUint) -> Result<BigUint> {
+        Ok(a.modpow(e, m))
+    }
+
+    fn mod_inverse(a: &BigUint, m: &BigUint) -> Result<BigUint> {
+        a.modinv(m)
+            .ok_or_else(|| PostError::InvalidParameters("element is not invertible".into()))
+    }
+
+    fn square_repeated(x: &BigUint, times: u64, n: &BigUint) -> Result<BigUint> {
+        let mut g = x.clone();
+        for _ in 0..times {
+            g = &g * &g % n;
+        }
+        Ok(g)
+    }
+}
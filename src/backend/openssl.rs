@@ -0,0 +1,89 @@
+use openssl::bn::{BigNum, BigNumContext};
+
+use crate::{backend::BigIntBackend, error::Result, rsa::square};
+
+pub struct OpenSsl;
+
+impl BigIntBackend for OpenSsl {
+    type Int = BigNum;
+
+    fn from_u64(a: u64) -> Result<BigNum> {
+        Ok(BigNum::from_slice(&a.to_be_bytes())?)
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<BigNum> {
+        Ok(BigNum::from_slice(bytes)?)
+    }
+
+    fn to_bytes(a: &BigNum) -> Result<Vec<u8>> {
+        Ok(a.to_vec())
+    }
+
+    fn generate_prime(bits: u32) -> Result<BigNum> {
+        let mut p = BigNum::new()?;
+        p.generate_prime(bits as i32, false, None, None)?;
+        Ok(p)
+    }
+
+    fn generate_safe_prime(bits: u32) -> Result<BigNum> {
+        let mut p = BigNum::new()?;
+        p.generate_prime(bits as i32, true, None, None)?;
+        Ok(p)
+    }
+
+    fn is_prime(a: &BigNum) -> Result<bool> {
+        let mut ctx = BigNumContext::new()?;
+        Ok(a.is_prime(64, &mut ctx)?)
+    }
+
+    fn add(a: &BigNum, b: &BigNum) -> Result<BigNum> {
+        let mut r = BigNum::new()?;
+        r.checked_add(a, b)?;
+        Ok(r)
+    }
+
+    fn sub(a: &BigNum, b: &BigNum) -> Result<BigNum> {
+        let mut r = BigNum::new()?;
+        r.checked_sub(a, b)?;
+        Ok(r)
+    }
+
+    fn mul(a: &BigNum, b: &BigNum) -> Result<BigNum> {
+        let mut ctx = BigNumContext::new()?;
+        let mut r = BigNum::new()?;
+        r.checked_mul(a, b, &mut ctx)?;
+        Ok(r)
+    }
+
+    fn rem(a: &BigNum, m: &BigNum) -> Result<BigNum> {
+        let mut ctx = BigNumContext::new()?;
+        let mut r = BigNum::new()?;
+        r.nnmod(a, m, &mut ctx)?;
+        Ok(r)
+    }
+
+    fn mod_mul(a: &BigNum, b: &BigNum, m: &BigNum) -> Result<BigNum> {
+        let mut ctx = BigNumContext::new()?;
+        let mut r = BigNum::new()?;
+        r.mod_mul(a, b, m, &mut ctx)?;
+        Ok(r)
+    }
+
+    fn mod_exp(a: &BigNum, e: &BigNum, m: &BigNum) -> Result<BigNum> {
+        let mut ctx = BigNumContext::new()?;
+        let mut r = BigNum::new()?;
+        r.mod_exp(a, e, m, &mut ctx)?;
+        Ok(r)
+    }
+
+    fn mod_inverse(a: &BigNum, m: &BigNum) -> Result<BigNum> {
+        let mut ctx = BigNumContext::new()?;
+        let mut r = BigNum::new()?;
+        r.mod_inverse(a, m, &mut ctx)?;
+        Ok(r)
+    }
+
+    fn square_repeated(x: &BigNum, times: u64, n: &BigNum) -> Result<BigNum> {
+        square(x, times, n, &mut BigNumContext::new()?)
+    }
+}
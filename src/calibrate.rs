@@ -0,0 +1,140 @@
+//! Deriving the squaring count and chain length from wall-clock targets.
+//!
+//! An operator asks for "prove storage every hour for 90 days"; [`Calibration`] measures how fast this machine squares
+//! and turns that into [`Params`] for [`Store`](crate::Store) and [`Prove`](crate::Prove).
+
+use std::time::{Duration, Instant};
+
+use crate::{
+    backend::{BigIntBackend, DefaultBackend},
+    error::{PostError, Result},
+    modulus::Modulus,
+    params::Params,
+};
+
+// Squarings per timing sample, so that the clock is read rarely.
+const BATCH: u64 = 1 << 12;
+
+/// The measured squaring rate of this machine for a modulus of `n_bits` bits.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Calibration {
+    pub n_bits: u32,
+    pub squarings_per_sec: f64,
+}
+
+impl Calibration {
+    /// Squares modulo `n` with the [`DefaultBackend`] for about `sample`.
+    pub fn measure(modulus: &Modulus, sample: Duration) -> Result<Self> {
+        Self::measure_in::<DefaultBackend>(modulus, sample)
+    }
+
+    /// Squares modulo `n` with backend `B` for about `sample`.
+    pub fn measure_in<B: BigIntBackend>(modulus: &Modulus, sample: Duration) -> Result<Self> {
+        let n = &B::from_bytes(modulus.as_bytes())?;
+        let mut g = B::rem(&B::from_u64(3)?, n)?;
+        let mut squarings = 0;
+        let now = Instant::now();
+        while squarings == 0 || now.elapsed() < sample {
+            g = B::square_repeated(&g, BATCH, n)?;
+            squarings += BATCH;
+        }
+        Ok(Self {
+            n_bits: modulus.bits(),
+            squarings_per_sec: squarings as f64 / now.elapsed().as_secs_f64(),
+        })
+    }
+
+    /// Picks the smallest squaring count for which a prover `speedup` times faster than this machine still spends at
+    /// least `interval` on every delay, and enough delays that such a prover cannot finish the chain within `duration`.
+    ///
+    /// The honest prover on this machine therefore needs about `speedup` times longer per round, see
+    /// [`Calibration::round_time`].
+    pub fn schedule(&self, interval: Duration, duration: Duration, speedup: f64) -> Result<Params> {
+        if interval.is_zero() || duration < interval {
+            return Err(PostError::InvalidParameters(format!(
+                "cannot fit an interval of {:?} into a duration of {:?}",
+                interval, duration
+            )));
+        }
+        if !(speedup >= 1.0 && speedup.is_finite()) {
+            return Err(PostError::InvalidParameters(format!(
+                "speedup factor {} is not at least 1",
+                speedup
+            )));
+        }
+        let squarings = interval.as_secs_f64() * self.squarings_per_sec * speedup;
+        Params::new(
+            self.n_bits,
+            duration.as_nanos().div_ceil(interval.as_nanos()) as usize,
+            squarings.ceil() as u64,
+        )
+    }
+
+    /// How long one delay of `squarings` squarings takes on this machine.
+    pub fn round_time(&self, squarings: u64) -> Duration {
+        Duration::from_secs_f64(squarings as f64 / self.squarings_per_sec)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::params::{MAX_ROUNDS, MAX_SQUARINGS};
+
+    const HOUR: Duration = Duration::from_secs(3600);
+
+    fn calibration() -> Calibration {
+        Calibration {
+            n_bits: 2048,
+            squarings_per_sec: 1000.0,
+        }
+    }
+
+    #[test]
+    fn schedule() {
+        let params = calibration().schedule(HOUR, 90 * 24 * HOUR, 1.0).unwrap();
+        assert_eq!(params.n_bits, 2048);
+        assert_eq!(params.k, 90 * 24);
+        assert_eq!(params.squarings, 3_600_000);
+        assert_eq!(calibration().round_time(params.squarings), HOUR);
+
+        // The margin scales the delay, and a partial interval at the end still needs a delay of its own.
+        let params = calibration()
+            .schedule(Duration::from_millis(1500), HOUR + HOUR / 2, 2.5)
+            .unwrap();
+        assert_eq!(params.k, 3600);
+        assert_eq!(params.squarings, 3750);
+        let params = calibration()
+            .schedule(HOUR, 2 * HOUR + Duration::from_nanos(1), 1.0)
+            .unwrap();
+        assert_eq!(params.k, 3);
+        // Fractional squarings are rounded up.
+        let params = calibration()
+            .schedule(Duration::from_micros(1500), HOUR, 1.0)
+            .unwrap();
+        assert_eq!(params.squarings, 2);
+    }
+
+    #[test]
+    fn rejects_invalid_targets() {
+        for (interval, duration, speedup) in [
+            (Duration::ZERO, HOUR, 1.0),
+            (HOUR, HOUR / 2, 1.0),
+            (HOUR, HOUR, 0.5),
+            (HOUR, HOUR, f64::NAN),
+            (HOUR, HOUR, f64::INFINITY),
+            // Beyond the bounds of `Params`.
+            (HOUR, HOUR * (MAX_ROUNDS as u32 + 1), 1.0),
+            (
+                Duration::from_secs(MAX_SQUARINGS / 1000 + 1),
+                Duration::from_secs(MAX_SQUARINGS / 1000 + 1),
+                1.0,
+            ),
+        ] {
+            assert!(matches!(
+                calibration().schedule(interval, duration, speedup),
+                Err(PostError::InvalidParameters(_))
+            ));
+        }
+    }
+}
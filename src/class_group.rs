@@ -0,0 +1,447 @@
+//! Repeated squaring in the class group of an imaginary quadratic order.
+//!
+//! The group is determined by a negative prime discriminant `D = -p`, `p = 7 mod 8`, derived from a public seed. Its
+//! order is unknown to everyone, so there is no trapdoor and no trusted setup: the verifier relies on Wesolowski proofs
+//! instead of [`DelayFunction::eval_trapdoor`].
+//!
+//! Elements are reduced binary quadratic forms `(a, b, c)` with `b^2 - 4ac = D`, encoded as `a` and `b` only.
+
+use std::cmp::Ordering;
+
+use openssl::bn::{BigNum, BigNumContext, BigNumRef};
+
+use crate::{
+    delay::DelayFunction,
+    error::{PostError, Result},
+    hash::sha3,
+    params::check_squarings,
+    vdf::{wesolowski, Group},
+};
+
+fn add(a: &BigNum, b: &BigNum) -> Result<BigNum> {
+    let mut r = BigNum::new()?;
+    r.checked_add(a, b)?;
+    Ok(r)
+}
+
+fn sub(a: &BigNum, b: &BigNum) -> Result<BigNum> {
+    let mut r = BigNum::new()?;
+    r.checked_sub(a, b)?;
+    Ok(r)
+}
+
+fn mul(a: &BigNum, b: &BigNum, ctx: &mut BigNumContext) -> Result<BigNum> {
+    let mut r = BigNum::new()?;
+    r.checked_mul(a, b, ctx)?;
+    Ok(r)
+}
+
+fn copy(a: &BigNumRef) -> Result<BigNum> {
+    Ok(a.to_owned()?)
+}
+
+fn neg(a: &BigNumRef) -> Result<BigNum> {
+    let mut r = a.to_owned()?;
+    r.set_negative(!a.is_negative());
+    Ok(r)
+}
+
+// Floor division, `b` positive or negative.
+fn fdiv(a: &BigNum, b: &BigNum, ctx: &mut BigNumContext) -> Result<(BigNum, BigNum)> {
+    let mut q = BigNum::new()?;
+    let mut r = BigNum::new()?;
+    q.div_rem(&mut r, a, b, ctx)?;
+    if r.num_bits() != 0 && r.is_negative() != b.is_negative() {
+        q.sub_word(1)?;
+        r = add(&r, b)?;
+    }
+    Ok((q, r))
+}
+
+fn nnmod(a: &BigNum, m: &BigNum, ctx: &mut BigNumContext) -> Result<BigNum> {
+    let mut r = BigNum::new()?;
+    r.nnmod(a, m, ctx)?;
+    Ok(r)
+}
+
+// Returns `(g, s)` with `a * s = g mod m`, `g = gcd(a, m) >= 0`.
+fn xgcd(a: &BigNum, m: &BigNum, ctx: &mut BigNumContext) -> Result<(BigNum, BigNum)> {
+    let (mut r0, mut r1) = (copy(a)?, copy(m)?);
+    let (mut s0, mut s1) = (BigNum::from_u32(1)?, BigNum::new()?);
+    while r1.num_bits() != 0 {
+        let (q, r) = fdiv(&r0, &r1, ctx)?;
+        let s = sub(&s0, &mul(&q, &s1, ctx)?)?;
+        r0 = std::mem::replace(&mut r1, r);
+        s0 = std::mem::replace(&mut s1, s);
+    }
+    if r0.is_negative() {
+        r0 = neg(&r0)?;
+        s0 = neg(&s0)?;
+    }
+    Ok((r0, s0))
+}
+
+// Solves `a * x = b mod m`; the solutions are `x0 + k * step`.
+fn solve_mod(
+    a: &BigNum,
+    b: &BigNum,
+    m: &BigNum,
+    ctx: &mut BigNumContext,
+) -> Result<(BigNum, BigNum)> {
+    let (g, s) = xgcd(a, m, ctx)?;
+    let (q, r) = fdiv(b, &g, ctx)?;
+    if r.num_bits() != 0 {
+        return Err(PostError::MalformedProof("forms cannot be composed".into()));
+    }
+    let x0 = nnmod(&mul(&q, &s, ctx)?, m, ctx)?;
+    let (step, _) = fdiv(m, &g, ctx)?;
+    Ok((x0, step))
+}
+
+/// A binary quadratic form `ax^2 + bxy + cy^2`.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Form {
+    pub a: BigNum,
+    pub b: BigNum,
+    pub c: BigNum,
+}
+
+impl Form {
+    fn to_owned(&self) -> Result<Form> {
+        Ok(Form {
+            a: copy(&self.a)?,
+            b: copy(&self.b)?,
+            c: copy(&self.c)?,
+        })
+    }
+
+    fn normalize(self, ctx: &mut BigNumContext) -> Result<Form> {
+        let Form { a, b, c } = self;
+        let neg_a = neg(&a)?;
+        if neg_a < b && b <= a {
+            return Ok(Form { a, b, c });
+        }
+        // r = floor((a - b) / 2a), b' = b + 2ra, c' = ar^2 + br + c
+        let two_a = add(&a, &a)?;
+        let (r, _) = fdiv(&sub(&a, &b)?, &two_a, ctx)?;
+        let b2 = add(&b, &mul(&r, &two_a, ctx)?)?;
+        let ar = mul(&a, &r, ctx)?;
+        let c2 = add(&mul(&add(&ar, &b)?, &r, ctx)?, &c)?;
+        Ok(Form { a, b: b2, c: c2 })
+    }
+
+    fn reduce(self, ctx: &mut BigNumContext) -> Result<Form> {
+        let mut f = self.normalize(ctx)?;
+        loop {
+            let Form { a, b, c } = &f;
+            match a.cmp(c) {
+                Ordering::Greater => {}
+                Ordering::Equal if b.is_negative() => {}
+                _ => return Ok(f),
+            }
+            // s = floor((c + b) / 2c), (a, b, c) = (c, -b + 2sc, cs^2 - bs + a)
+            let two_c = add(c, c)?;
+            let (s, _) = fdiv(&add(c, b)?, &two_c, ctx)?;
+            let b2 = sub(&mul(&s, &two_c, ctx)?, b)?;
+            let cs = mul(c, &s, ctx)?;
+            let c2 = add(&mul(&sub(&cs, b)?, &s, ctx)?, a)?;
+            f = Form {
+                a: copy(c)?,
+                b: b2,
+                c: c2,
+            };
+        }
+    }
+}
+
+/// The class group of discriminant `D`.
+pub struct ClassGroup {
+    d: BigNum,
+}
+
+impl ClassGroup {
+    /// Derives a discriminant of `bits` bits from `seed`, such that nobody knows the order of the group.
+    pub fn from_seed(seed: &[u8], bits: usize) -> Result<Self> {
+        if bits < 64 {
+            return Err(PostError::InvalidParameters(format!(
+                "discriminant of {} bits is too small",
+                bits
+            )));
+        }
+        let mut ctx = BigNumContext::new()?;
+        for counter in 0u64.. {
+            let mut bytes = vec![];
+            for block in 0u64.. {
+                if bytes.len() * 8 >= bits {
+                    break;
+                }
+                bytes.extend_from_slice(&sha3(
+                    &[
+                        b"discriminant".as_ref(),
+                        seed,
+                        &counter.to_be_bytes(),
+                        &block.to_be_bytes(),
+                    ]
+                    .concat(),
+                )?);
+            }
+            bytes.truncate(bits.div_ceil(8));
+            bytes[0] &= 0xff >> (bytes.len() * 8 - bits);
+            let mut p = BigNum::from_slice(&bytes)?;
+            p.set_bit(bits as i32 - 1)?;
+            for i in 0..3 {
+                p.set_bit(i)?;
+            }
+            if p.is_prime(64, &mut ctx)? {
+                return Ok(Self { d: neg(&p)? });
+            }
+        }
+        unreachable!()
+    }
+
+    pub fn discriminant(&self) -> &BigNum {
+        &self.d
+    }
+
+    // c = (b^2 - D) / 4a
+    fn form(&self, a: BigNum, b: BigNum, ctx: &mut BigNumContext) -> Result<Form> {
+        let (c, r) = fdiv(
+            &sub(&mul(&b, &b, ctx)?, &self.d)?,
+            &mul(&a, &BigNum::from_u32(4)?, ctx)?,
+            ctx,
+        )?;
+        if r.num_bits() != 0 {
+            return Err(PostError::MalformedProof(
+                "form does not have the group discriminant".into(),
+            ));
+        }
+        Ok(Form { a, b, c })
+    }
+
+    /// Maps `x` to a form `(a, b, c)` with `a` a prime `= 3 mod 4` for which `D` is a square.
+    pub fn hash_to_form(&self, x: &[u8]) -> Result<Form> {
+        let mut ctx = BigNumContext::new()?;
+        for counter in 0u64.. {
+            let h = sha3(&[b"hash-to-form".as_ref(), x, &counter.to_be_bytes()].concat())?;
+            let mut a = BigNum::from_slice(&h[..16])?;
+            a.set_bit(127)?;
+            a.set_bit(0)?;
+            a.set_bit(1)?;
+            if !a.is_prime(64, &mut ctx)? {
+                continue;
+            }
+            let d = nnmod(&self.d, &a, &mut ctx)?;
+            // Euler's criterion, then the square root for a = 3 mod 4.
+            let mut a1 = copy(&a)?;
+            a1.sub_word(1)?;
+            let mut e = BigNum::new()?;
+            e.rshift1(&a1)?;
+            let mut l = BigNum::new()?;
+            l.mod_exp(&d, &e, &a, &mut ctx)?;
+            if l != BigNum::from_u32(1)? {
+                continue;
+            }
+            let mut a1 = copy(&a)?;
+            a1.add_word(1)?;
+            let mut e = BigNum::new()?;
+            e.rshift(&a1, 2)?;
+            let mut b = BigNum::new()?;
+            b.mod_exp(&d, &e, &a, &mut ctx)?;
+            if !b.is_bit_set(0) {
+                b = sub(&a, &b)?;
+            }
+            return self.form(a, b, &mut ctx)?.reduce(&mut ctx);
+        }
+        unreachable!()
+    }
+
+    // Both coordinates of a reduced form are below sqrt(|D|).
+    fn width(&self) -> usize {
+        (self.d.num_bits() as usize).div_ceil(16) + 1
+    }
+}
+
+impl Group for ClassGroup {
+    type Element = Form;
+
+    fn identity(&self) -> Result<Form> {
+        let mut ctx = BigNumContext::new()?;
+        self.form(BigNum::from_u32(1)?, BigNum::from_u32(1)?, &mut ctx)
+    }
+
+    fn mul(&self, f1: &Form, f2: &Form) -> Result<Form> {
+        let mut ctx = BigNumContext::new()?;
+        let ctx = &mut ctx;
+        let (a1, b1, c1) = (&f1.a, &f1.b, &f1.c);
+        let (a2, b2) = (&f2.a, &f2.b);
+        let two = BigNum::from_u32(2)?;
+        let (g, _) = fdiv(&add(b1, b2)?, &two, ctx)?;
+        let (h, _) = fdiv(&sub(b2, b1)?, &two, ctx)?;
+        let mut w = BigNum::new()?;
+        w.gcd(a1, a2, ctx)?;
+        let mut w3 = BigNum::new()?;
+        w3.gcd(&w, &g, ctx)?;
+        let w = w3;
+        let (s, _) = fdiv(a1, &w, ctx)?;
+        let (t, _) = fdiv(a2, &w, ctx)?;
+        let (u, _) = fdiv(&g, &w, ctx)?;
+        // Solve k t - l s = h, k u - m s = c2, l u - m t = c1 for k, l, m.
+        let tu = mul(&t, &u, ctx)?;
+        let st = mul(&s, &t, ctx)?;
+        let hu = mul(&h, &u, ctx)?;
+        let sc = mul(&s, c1, ctx)?;
+        let (k0, step) = solve_mod(&tu, &add(&hu, &sc)?, &st, ctx)?;
+        let (n, _) = solve_mod(
+            &mul(&t, &step, ctx)?,
+            &sub(&h, &mul(&t, &k0, ctx)?)?,
+            &s,
+            ctx,
+        )?;
+        let k = add(&k0, &mul(&step, &n, ctx)?)?;
+        let (l, _) = fdiv(&sub(&mul(&t, &k, ctx)?, &h)?, &s, ctx)?;
+        let (m, _) = fdiv(&sub(&sub(&mul(&tu, &k, ctx)?, &hu)?, &sc)?, &st, ctx)?;
+        let a3 = st;
+        let b3 = sub(
+            &mul(&w, &u, ctx)?,
+            &add(&mul(&k, &t, ctx)?, &mul(&l, &s, ctx)?)?,
+        )?;
+        let c3 = sub(&mul(&k, &l, ctx)?, &mul(&w, &m, ctx)?)?;
+        Form {
+            a: a3,
+            b: b3,
+            c: c3,
+        }
+        .reduce(ctx)
+    }
+
+    fn encode(&self, f: &Form) -> Result<Vec<u8>> {
+        let w = self.width() as i32;
+        let mut bytes = f.a.to_vec_padded(w)?;
+        bytes.push(f.b.is_negative() as u8);
+        bytes.extend_from_slice(&f.b.to_vec_padded(w)?);
+        Ok(bytes)
+    }
+
+    fn decode(&self, bytes: &[u8]) -> Result<Form> {
+        let w = self.width();
+        if bytes.len() != 2 * w + 1 || bytes[w] > 1 {
+            return Err(PostError::MalformedProof("invalid form encoding".into()));
+        }
+        let a = BigNum::from_slice(&bytes[..w])?;
+        let mut b = BigNum::from_slice(&bytes[w + 1..])?;
+        b.set_negative(bytes[w] == 1);
+        if a.num_bits() == 0 || a.is_negative() {
+            return Err(PostError::MalformedProof("invalid form encoding".into()));
+        }
+        let mut ctx = BigNumContext::new()?;
+        let f = self.form(a, b, &mut ctx)?;
+        if f.to_owned()?.reduce(&mut ctx)? != f {
+            return Err(PostError::MalformedProof("form is not reduced".into()));
+        }
+        Ok(f)
+    }
+}
+
+/// Repeated squaring `x -> H(x)^(2^T)` in a class group, proved with Wesolowski proofs.
+pub struct ClassGroupSquaring {
+    group: ClassGroup,
+    squarings: u64,
+}
+
+impl ClassGroupSquaring {
+    pub fn new(group: ClassGroup, squarings: u64) -> Result<Self> {
+        check_squarings(squarings)?;
+        Ok(Self { group, squarings })
+    }
+
+    pub fn group(&self) -> &ClassGroup {
+        &self.group
+    }
+
+    fn square(&self, f: Form) -> Result<Form> {
+        let mut f = f;
+        for _ in 0..self.squarings {
+            f = self.group.square(&f)?;
+        }
+        Ok(f)
+    }
+}
+
+impl DelayFunction for ClassGroupSquaring {
+    fn eval(&self, x: &[u8]) -> Result<Vec<u8>> {
+        self.group
+            .encode(&self.square(self.group.hash_to_form(x)?)?)
+    }
+
+    fn prove(&self, x: &[u8]) -> Result<(Vec<u8>, Vec<u8>)> {
+        let x = self.group.hash_to_form(x)?;
+        let y = self.square(x.to_owned()?)?;
+        let proof = wesolowski::prove(&self.group, &x, &y, self.squarings)?;
+        Ok((self.group.encode(&y)?, proof))
+    }
+
+    fn verify(&self, x: &[u8], y: &[u8], proof: &[u8]) -> Result<bool> {
+        let x = self.group.hash_to_form(x)?;
+        let y = self.group.decode(y)?;
+        wesolowski::verify(&self.group, &x, &y, proof, self.squarings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn group() -> ClassGroup {
+        ClassGroup::from_seed(b"test seed", 512).unwrap()
+    }
+
+    #[test]
+    fn group_law() {
+        let group = group();
+        let x = group.hash_to_form(b"x").unwrap();
+        let one = group.identity().unwrap();
+        assert_eq!(group.mul(&x, &one).unwrap(), x);
+        assert_eq!(group.mul(&one, &x).unwrap(), x);
+        let x2 = group.square(&x).unwrap();
+        assert_eq!(group.mul(&x, &x).unwrap(), x2);
+        let x3 = group.mul(&x2, &x).unwrap();
+        assert_eq!(group.mul(&x, &x2).unwrap(), x3);
+        let e = BigNum::from_u32(3).unwrap();
+        assert_eq!(group.pow(&x, &e).unwrap(), x3);
+        assert_eq!(group.decode(&group.encode(&x3).unwrap()).unwrap(), x3);
+    }
+
+    #[test]
+    fn roundtrip() {
+        for squarings in [1, 10, 100] {
+            let delay = ClassGroupSquaring::new(group(), squarings).unwrap();
+            let (y, proof) = delay.prove(b"x").unwrap();
+            assert_eq!(y, delay.eval(b"x").unwrap());
+            assert!(delay.verify(b"x", &y, &proof).unwrap());
+            assert!(!delay.verify(b"other", &y, &proof).unwrap());
+        }
+    }
+
+    #[test]
+    fn rejects_tampering() {
+        let delay = ClassGroupSquaring::new(group(), 100).unwrap();
+        let (y, proof) = delay.prove(b"x").unwrap();
+        let (other, _) = delay.prove(b"other").unwrap();
+        assert!(!delay.verify(b"x", &other, &proof).unwrap());
+        // Tampered encodings are either not reduced forms of the discriminant or the wrong ones.
+        for i in [0, y.len() / 2, y.len() - 1] {
+            let mut forged = y.clone();
+            forged[i] ^= 1;
+            assert!(!delay.verify(b"x", &forged, &proof).unwrap_or(false));
+            let mut forged = proof.clone();
+            forged[i] ^= 1;
+            assert!(!delay.verify(b"x", &y, &forged).unwrap_or(false));
+        }
+        assert!(delay.verify(b"x", &y, &proof[1..]).is_err());
+    }
+
+    #[test]
+    fn rejects_small_discriminants() {
+        assert!(ClassGroup::from_seed(b"seed", 32).is_err());
+    }
+}
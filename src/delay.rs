@@ -0,0 +1,43 @@
+use crate::error::{PostError, Result};
+
+/// An inherently sequential function applied between rounds.
+///
+/// The prover runs [`eval`](DelayFunction::eval); the verifier either holds a trapdoor for
+/// [`eval_trapdoor`](DelayFunction::eval_trapdoor), or checks the proofs emitted by
+/// [`prove`](DelayFunction::prove) with [`verify`](DelayFunction::verify).
+pub trait DelayFunction {
+    /// The slow, public evaluation.
+    fn eval(&self, x: &[u8]) -> Result<Vec<u8>>;
+
+    /// The fast evaluation available to whoever holds the secret trapdoor.
+    fn eval_trapdoor(&self, _x: &[u8]) -> Result<Vec<u8>> {
+        Err(PostError::Unsupported("evaluation without a trapdoor"))
+    }
+
+    /// Evaluates on `x` and proves the output, returning `(y, proof)`.
+    fn prove(&self, _x: &[u8]) -> Result<(Vec<u8>, Vec<u8>)> {
+        Err(PostError::Unsupported("delay proofs"))
+    }
+
+    fn verify(&self, _x: &[u8], _y: &[u8], _proof: &[u8]) -> Result<bool> {
+        Err(PostError::Unsupported("delay proofs"))
+    }
+}
+
+impl<D: DelayFunction + ?Sized> DelayFunction for &D {
+    fn eval(&self, x: &[u8]) -> Result<Vec<u8>> {
+        (**self).eval(x)
+    }
+
+    fn eval_trapdoor(&self, x: &[u8]) -> Result<Vec<u8>> {
+        (**self).eval_trapdoor(x)
+    }
+
+    fn prove(&self, x: &[u8]) -> Result<(Vec<u8>, Vec<u8>)> {
+        (**self).prove(x)
+    }
+
+    fn verify(&self, x: &[u8], y: &[u8], proof: &[u8]) -> Result<bool> {
+        (**self).verify(x, y, proof)
+    }
+}
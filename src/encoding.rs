@@ -0,0 +1,508 @@
+//! Versioned encodings of everything that crosses a process boundary.
+//!
+//! The binary form is the magic `PoST`, a type byte, a version byte and the bincode of the fields. The JSON form is an
+//! object with `type` and `version` next to the fields, with big integers and digests as hex strings.
+
+use bincode::Options;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{
+    audit::Message,
+    error::{PostError, Result},
+    hash::Suite,
+    modulus::{Modulus, Trapdoor},
+    net::Hello,
+    params::{Params, PublicParams},
+    post::{ChainProof, RoundProof, Tag},
+    transcript::{RoundOpening, Transcript, TranscriptCommitment, TranscriptRound},
+};
+
+const MAGIC: &[u8; 4] = b"PoST";
+const VERSION: u8 = 1;
+
+/// Binary and JSON encodings, checked for a known type and version on the way in.
+pub trait Encoding: wire::Wire {
+    fn to_binary(&self) -> Result<Vec<u8>> {
+        let body = codec()
+            .serialize(&self.to_repr()?)
+            .map_err(encoding_error)?;
+        Ok([MAGIC.as_ref(), &[Self::TAG, VERSION], &body].concat())
+    }
+
+    fn from_binary(bytes: &[u8]) -> Result<Self> {
+        let body = match bytes {
+            [m0, m1, m2, m3, tag, version, body @ ..] if [*m0, *m1, *m2, *m3] == *MAGIC => {
+                check_header(*tag == Self::TAG, *version)?;
+                body
+            }
+            _ => return Err(PostError::Encoding("missing PoST header".into())),
+        };
+        Self::from_repr(codec().deserialize(body).map_err(encoding_error)?)
+    }
+
+    fn to_json(&self) -> Result<String> {
+        serde_json::to_string_pretty(&Envelope {
+            kind: Self::KIND.into(),
+            version: VERSION,
+            body: self.to_repr()?,
+        })
+        .map_err(encoding_error)
+    }
+
+    fn from_json(json: &str) -> Result<Self> {
+        let header: Envelope<serde::de::IgnoredAny> =
+            serde_json::from_str(json).map_err(encoding_error)?;
+        check_header(header.kind == Self::KIND, header.version)?;
+        let envelope: Envelope<Self::Repr> = serde_json::from_str(json).map_err(encoding_error)?;
+        Self::from_repr(envelope.body)
+    }
+}
+
+impl<T: wire::Wire> Encoding for T {}
+
+mod wire {
+    use super::*;
+
+    /// Maps a type to the serde representation of its current version.
+    pub trait Wire: Sized {
+        const KIND: &'static str;
+        const TAG: u8;
+        type Repr: Serialize + DeserializeOwned;
+
+        fn to_repr(&self) -> Result<Self::Repr>;
+
+        fn from_repr(repr: Self::Repr) -> Result<Self>;
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Envelope<T> {
+    #[serde(rename = "type")]
+    kind: String,
+    version: u8,
+    #[serde(flatten)]
+    body: T,
+}
+
+fn codec() -> impl Options {
+    bincode::options().reject_trailing_bytes()
+}
+
+fn check_header(kind_matches: bool, version: u8) -> Result<()> {
+    if !kind_matches {
+        return Err(PostError::Encoding("unexpected type".into()));
+    }
+    if version != VERSION {
+        return Err(PostError::Encoding(format!(
+            "unsupported version {}",
+            version
+        )));
+    }
+    Ok(())
+}
+
+fn encoding_error(e: impl std::fmt::Display) -> PostError {
+    PostError::Encoding(e.to_string())
+}
+
+// Hex strings in human-readable formats, plain byte sequences otherwise.
+mod bytes {
+    use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(bytes: &Vec<u8>, s: S) -> Result<S::Ok, S::Error> {
+        if s.is_human_readable() {
+            s.serialize_str(&hex::encode(bytes))
+        } else {
+            bytes.serialize(s)
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Vec<u8>, D::Error> {
+        if d.is_human_readable() {
+            hex::decode(String::deserialize(d)?).map_err(D::Error::custom)
+        } else {
+            Vec::deserialize(d)
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Bytes(#[serde(with = "bytes")] Vec<u8>);
+
+fn suite(name: &str) -> Result<Suite> {
+    Suite::from_name(name).map_err(|_| PostError::Encoding(format!("unknown hash suite {}", name)))
+}
+
+fn index(i: u64) -> Result<usize> {
+    usize::try_from(i).map_err(|_| PostError::Encoding(format!("round {} out of range", i)))
+}
+
+fn check_digest(name: &str, digest: &[u8]) -> Result<()> {
+    if digest.len() != 32 {
+        return Err(PostError::Encoding(format!(
+            "{} is not a 32-byte digest",
+            name
+        )));
+    }
+    Ok(())
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct PublicParamsV1 {
+    #[serde(with = "bytes")]
+    n: Vec<u8>,
+    no_small_order: bool,
+    n_bits: u32,
+    k: u64,
+    squarings: u64,
+    suite: String,
+}
+
+impl wire::Wire for PublicParams {
+    const KIND: &'static str = "params";
+    const TAG: u8 = 1;
+    type Repr = PublicParamsV1;
+
+    fn to_repr(&self) -> Result<PublicParamsV1> {
+        Ok(PublicParamsV1 {
+            n: self.modulus.as_bytes().to_vec(),
+            no_small_order: self.modulus.no_small_order(),
+            n_bits: self.params.n_bits,
+            k: self.params.k as u64,
+            squarings: self.params.squarings,
+            suite: self.params.suite.name().into(),
+        })
+    }
+
+    fn from_repr(repr: PublicParamsV1) -> Result<Self> {
+        let modulus = Modulus::from_bytes(&repr.n)?;
+        let modulus = if repr.no_small_order {
+            modulus.with_no_small_order()
+        } else {
+            modulus
+        };
+        let k = usize::try_from(repr.k)
+            .map_err(|_| PostError::InvalidParameters(format!("{} delays", repr.k)))?;
+        let params = Params::new(repr.n_bits, k, repr.squarings)?.with_suite(suite(&repr.suite)?);
+        PublicParams::new(modulus, params)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct TrapdoorV1 {
+    #[serde(with = "bytes")]
+    p: Vec<u8>,
+    #[serde(with = "bytes")]
+    q: Vec<u8>,
+    no_small_order: bool,
+}
+
+impl wire::Wire for Trapdoor {
+    const KIND: &'static str = "trapdoor";
+    const TAG: u8 = 2;
+    type Repr = TrapdoorV1;
+
+    fn to_repr(&self) -> Result<TrapdoorV1> {
+        Ok(TrapdoorV1 {
+            p: self.p.clone(),
+            q: self.q.clone(),
+            no_small_order: self.no_small_order(),
+        })
+    }
+
+    fn from_repr(repr: TrapdoorV1) -> Result<Self> {
+        let trapdoor = Trapdoor::from_bytes(&repr.p, &repr.q)?;
+        if repr.no_small_order {
+            trapdoor.with_no_small_order()
+        } else {
+            Ok(trapdoor)
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct TagV1 {
+    #[serde(with = "bytes")]
+    cs: Vec<u8>,
+    #[serde(with = "bytes")]
+    vs: Vec<u8>,
+    suite: String,
+}
+
+// Also the prover's `Response`.
+impl wire::Wire for Tag {
+    const KIND: &'static str = "tag";
+    const TAG: u8 = 3;
+    type Repr = TagV1;
+
+    fn to_repr(&self) -> Result<TagV1> {
+        Ok(TagV1 {
+            cs: self.cs.clone(),
+            vs: self.vs.clone(),
+            suite: self.suite.name().into(),
+        })
+    }
+
+    fn from_repr(repr: TagV1) -> Result<Self> {
+        check_digest("cs", &repr.cs)?;
+        check_digest("vs", &repr.vs)?;
+        Ok(Tag {
+            cs: repr.cs,
+            vs: repr.vs,
+            suite: suite(&repr.suite)?,
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct RoundProofV1 {
+    #[serde(with = "bytes")]
+    v: Vec<u8>,
+    #[serde(with = "bytes")]
+    y: Vec<u8>,
+    #[serde(with = "bytes")]
+    proof: Vec<u8>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ChainProofV1 {
+    rounds: Vec<RoundProofV1>,
+}
+
+impl wire::Wire for ChainProof {
+    const KIND: &'static str = "chain-proof";
+    const TAG: u8 = 4;
+    type Repr = ChainProofV1;
+
+    fn to_repr(&self) -> Result<ChainProofV1> {
+        Ok(ChainProofV1 {
+            rounds: self
+                .rounds
+                .iter()
+                .map(|r| RoundProofV1 {
+                    v: r.v.clone(),
+                    y: r.y.clone(),
+                    proof: r.proof.clone(),
+                })
+                .collect(),
+        })
+    }
+
+    fn from_repr(repr: ChainProofV1) -> Result<Self> {
+        Ok(ChainProof {
+            rounds: repr
+                .rounds
+                .into_iter()
+                .map(|r| RoundProof {
+                    v: r.v,
+                    y: r.y,
+                    proof: r.proof,
+                })
+                .collect(),
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct TranscriptRoundV1 {
+    #[serde(with = "bytes")]
+    c: Vec<u8>,
+    #[serde(with = "bytes")]
+    v: Vec<u8>,
+    y: Option<Bytes>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct TranscriptV1 {
+    suite: String,
+    rounds: Vec<TranscriptRoundV1>,
+}
+
+impl wire::Wire for Transcript {
+    const KIND: &'static str = "transcript";
+    const TAG: u8 = 5;
+    type Repr = TranscriptV1;
+
+    fn to_repr(&self) -> Result<TranscriptV1> {
+        Ok(TranscriptV1 {
+            suite: self.suite.name().into(),
+            rounds: self
+                .rounds
+                .iter()
+                .map(|r| TranscriptRoundV1 {
+                    c: r.c.clone(),
+                    v: r.v.clone(),
+                    y: r.y.clone().map(Bytes),
+                })
+                .collect(),
+        })
+    }
+
+    fn from_repr(repr: TranscriptV1) -> Result<Self> {
+        Ok(Transcript {
+            suite: suite(&repr.suite)?,
+            rounds: repr
+                .rounds
+                .into_iter()
+                .map(|r| TranscriptRound {
+                    c: r.c,
+                    v: r.v,
+                    y: r.y.map(|Bytes(y)| y),
+                })
+                .collect(),
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct TranscriptCommitmentV1 {
+    #[serde(with = "bytes")]
+    root: Vec<u8>,
+    rounds: u64,
+    suite: String,
+}
+
+impl wire::Wire for TranscriptCommitment {
+    const KIND: &'static str = "transcript-commitment";
+    const TAG: u8 = 6;
+    type Repr = TranscriptCommitmentV1;
+
+    fn to_repr(&self) -> Result<TranscriptCommitmentV1> {
+        Ok(TranscriptCommitmentV1 {
+            root: self.root.clone(),
+            rounds: self.rounds as u64,
+            suite: self.suite.name().into(),
+        })
+    }
+
+    fn from_repr(repr: TranscriptCommitmentV1) -> Result<Self> {
+        check_digest("root", &repr.root)?;
+        Ok(TranscriptCommitment {
+            root: repr.root,
+            rounds: index(repr.rounds)?,
+            suite: suite(&repr.suite)?,
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct RoundOpeningV1 {
+    round: u64,
+    #[serde(with = "bytes")]
+    c: Vec<u8>,
+    #[serde(with = "bytes")]
+    v: Vec<u8>,
+    path: Vec<Bytes>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct RoundOpeningsV1 {
+    openings: Vec<RoundOpeningV1>,
+}
+
+impl wire::Wire for Vec<RoundOpening> {
+    const KIND: &'static str = "round-openings";
+    const TAG: u8 = 7;
+    type Repr = RoundOpeningsV1;
+
+    fn to_repr(&self) -> Result<RoundOpeningsV1> {
+        Ok(RoundOpeningsV1 {
+            openings: self
+                .iter()
+                .map(|o| RoundOpeningV1 {
+                    round: o.round as u64,
+                    c: o.c.clone(),
+                    v: o.v.clone(),
+                    path: o.path.iter().cloned().map(Bytes).collect(),
+                })
+                .collect(),
+        })
+    }
+
+    fn from_repr(repr: RoundOpeningsV1) -> Result<Self> {
+        repr.openings
+            .into_iter()
+            .map(|o| {
+                Ok(RoundOpening {
+                    round: index(o.round)?,
+                    c: o.c,
+                    v: o.v,
+                    path: o.path.into_iter().map(|Bytes(h)| h).collect(),
+                })
+            })
+            .collect()
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum MessageV1 {
+    Challenge {
+        #[serde(with = "bytes")]
+        c: Vec<u8>,
+    },
+    Checkpoint {
+        round: u64,
+        #[serde(with = "bytes")]
+        c: Vec<u8>,
+        #[serde(with = "bytes")]
+        v: Vec<u8>,
+    },
+    Response(TagV1),
+}
+
+impl wire::Wire for Message {
+    const KIND: &'static str = "audit-message";
+    const TAG: u8 = 8;
+    type Repr = MessageV1;
+
+    fn to_repr(&self) -> Result<MessageV1> {
+        Ok(match self {
+            Message::Challenge { c } => MessageV1::Challenge { c: c.clone() },
+            Message::Checkpoint { round, c, v } => MessageV1::Checkpoint {
+                round: *round as u64,
+                c: c.clone(),
+                v: v.clone(),
+            },
+            Message::Response(response) => MessageV1::Response(wire::Wire::to_repr(response)?),
+        })
+    }
+
+    fn from_repr(repr: MessageV1) -> Result<Self> {
+        Ok(match repr {
+            MessageV1::Challenge { c } => Message::Challenge { c },
+            MessageV1::Checkpoint { round, c, v } => Message::Checkpoint {
+                round: index(round)?,
+                c,
+                v,
+            },
+            MessageV1::Response(tag) => Message::Response(wire::Wire::from_repr(tag)?),
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct HelloV1 {
+    file: String,
+    every: u64,
+}
+
+impl wire::Wire for Hello {
+    const KIND: &'static str = "hello";
+    const TAG: u8 = 9;
+    type Repr = HelloV1;
+
+    fn to_repr(&self) -> Result<HelloV1> {
+        Ok(HelloV1 {
+            file: self.file.clone(),
+            every: self.every as u64,
+        })
+    }
+
+    fn from_repr(repr: HelloV1) -> Result<Self> {
+        Ok(Hello {
+            file: repr.file,
+            every: index(repr.every)?,
+        })
+    }
+}
@@ -0,0 +1,70 @@
+use std::{fmt, io};
+
+#[cfg(feature = "openssl")]
+use openssl::error::ErrorStack;
+
+#[derive(Debug)]
+pub enum PostError {
+    /// An OpenSSL primitive (prime generation, bignum arithmetic) failed.
+    #[cfg(feature = "openssl")]
+    Ssl(ErrorStack),
+    Io(io::Error),
+    InvalidParameters(String),
+    MalformedProof(String),
+    /// A serialized key, tag or proof could not be decoded.
+    Encoding(String),
+    VerificationFailed,
+    /// The requested operation is not provided by the chosen backend.
+    Unsupported(&'static str),
+    /// A peer sent a message that the audit protocol does not allow at this point.
+    Protocol(String),
+}
+
+pub type Result<T> = std::result::Result<T, PostError>;
+
+impl fmt::Display for PostError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            #[cfg(feature = "openssl")]
+            PostError::Ssl(e) => write!(f, "openssl error: {}", e),
+            PostError::Io(e) => write!(f, "i/o error: {}", e),
+            PostError::InvalidParameters(s) => write!(f, "invalid parameters: {}", s),
+            PostError::MalformedProof(s) => write!(f, "malformed proof: {}", s),
+            PostError::Encoding(s) => write!(f, "malformed encoding: {}", s),
+            PostError::VerificationFailed => write!(f, "verification failed"),
+            PostError::Unsupported(s) => write!(f, "unsupported: {}", s),
+            PostError::Protocol(s) => write!(f, "protocol violation: {}", s),
+        }
+    }
+}
+
+impl std::error::Error for PostError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            #[cfg(feature = "openssl")]
+            PostError::Ssl(e) => Some(e),
+            PostError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "openssl")]
+impl From<ErrorStack> for PostError {
+    fn from(e: ErrorStack) -> Self {
+        PostError::Ssl(e)
+    }
+}
+
+impl From<io::Error> for PostError {
+    fn from(e: io::Error) -> Self {
+        PostError::Io(e)
+    }
+}
+
+// The system's random number generator failed.
+impl From<getrandom::Error> for PostError {
+    fn from(e: getrandom::Error) -> Self {
+        PostError::Io(e.into())
+    }
+}
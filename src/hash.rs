@@ -0,0 +1,365 @@
+use sha2::{Digest as _, Sha256};
+use tiny_keccak::{CShake, Hasher as _, Kmac, Sha3};
+
+use crate::{
+    error::{PostError, Result},
+    source::Source,
+};
+
+// Plain SHA3-256, for the hash trees of Merkle commitments and PoSW labels, and the class group parameters.
+pub(crate) fn sha3(data: &[u8]) -> Result<[u8; 32]> {
+    let mut h = Sha3::v256();
+    h.update(data);
+    let mut out = [0; 32];
+    h.finalize(&mut out);
+    Ok(out)
+}
+
+// The suite of the digests that are not part of a chain and so cannot follow its suite: the PRF of a PoR key and the
+// challenges inside delay proofs, which are checked without knowing the chain.
+pub(crate) const FIXED_SUITE: Suite = Suite::Kmac256;
+
+const HMAC_BLOCK_BYTES: usize = 64;
+
+/// The hash function behind the round MAC, the challenge derivation and the transcript digests of a chain.
+///
+/// Every suite but [`Suite::Sha3`] binds a [`Domain`] into each digest, so that a value computed for one role is never
+/// accepted for another. All digests are 32 bytes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Suite {
+    /// `sha3(data)` and `sha3(key || data)` without domain separation, as in the original implementation. Only for
+    /// chains; the digests outside a chain never use it.
+    Sha3,
+    /// KMAC256 keyed by the MAC key, and cSHAKE256 for plain digests, with the domain as customization string.
+    #[default]
+    Kmac256,
+    /// HMAC-SHA256, and SHA-256 for plain digests, over the length-prefixed domain followed by the data.
+    HmacSha256,
+    /// BLAKE3 in key derivation mode with the domain as context, keyed with a key derived from the MAC key.
+    Blake3,
+}
+
+/// The role of a digest in the chain.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Domain {
+    /// The round response, keyed by the round challenge.
+    RoundMac,
+    /// The delay function input hashed from a round response.
+    DelayInput,
+    /// The next round challenge hashed from a delay output, and the samples a round challenge is expanded into.
+    Challenge,
+    /// The digests of all challenges and all round responses.
+    Transcript,
+    /// The Fiat-Shamir challenges inside delay proofs.
+    ProofChallenge,
+    /// The PRF of the block authenticators of a proof of retrievability.
+    Authenticator,
+}
+
+impl Domain {
+    fn context(self) -> &'static str {
+        match self {
+            Domain::RoundMac => "PoST 2020 round MAC",
+            Domain::DelayInput => "PoST 2020 delay input",
+            Domain::Challenge => "PoST 2020 challenge derivation",
+            Domain::Transcript => "PoST 2020 transcript digest",
+            Domain::ProofChallenge => "PoST 2020 proof challenge",
+            Domain::Authenticator => "PoST 2020 PoR authenticator",
+        }
+    }
+}
+
+impl Suite {
+    pub const ALL: [Suite; 4] = [
+        Suite::Sha3,
+        Suite::Kmac256,
+        Suite::HmacSha256,
+        Suite::Blake3,
+    ];
+
+    /// The name used in encodings and on the command line.
+    pub fn name(self) -> &'static str {
+        match self {
+            Suite::Sha3 => "sha3",
+            Suite::Kmac256 => "kmac256",
+            Suite::HmacSha256 => "hmac-sha256",
+            Suite::Blake3 => "blake3",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Result<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|suite| suite.name() == name)
+            .ok_or_else(|| PostError::InvalidParameters(format!("unknown hash suite {}", name)))
+    }
+
+    /// A plain digest for `domain`.
+    pub fn hasher(self, domain: Domain) -> Result<Mac> {
+        let context = domain.context();
+        Ok(Mac(match self {
+            Suite::Sha3 => State::Keccak(Box::new(KeccakState::Sha3(Sha3::v256()))),
+            Suite::Kmac256 => State::Keccak(Box::new(KeccakState::CShake(CShake::v256(
+                b"",
+                context.as_bytes(),
+            )))),
+            Suite::HmacSha256 => {
+                let mut h = Sha256::new();
+                update_domain(&mut h, context);
+                State::Sha256(h)
+            }
+            Suite::Blake3 => State::Blake3(Box::new(blake3::Hasher::new_derive_key(context))),
+        }))
+    }
+
+    /// A MAC under `key` for `domain`.
+    pub fn keyed(self, domain: Domain, key: &[u8]) -> Result<Mac> {
+        let context = domain.context();
+        Ok(Mac(match self {
+            Suite::Sha3 => {
+                let mut h = Sha3::v256();
+                h.update(key);
+                State::Keccak(Box::new(KeccakState::Sha3(h)))
+            }
+            Suite::Kmac256 => State::Keccak(Box::new(KeccakState::Kmac(Kmac::v256(
+                key,
+                context.as_bytes(),
+            )))),
+            Suite::HmacSha256 => {
+                let mut block = [0; HMAC_BLOCK_BYTES];
+                if key.len() > HMAC_BLOCK_BYTES {
+                    block[..32].copy_from_slice(&Sha256::digest(key));
+                } else {
+                    block[..key.len()].copy_from_slice(key);
+                }
+                let mut inner = Sha256::new();
+                inner.update(block.map(|b| b ^ 0x36));
+                update_domain(&mut inner, context);
+                State::HmacSha256 {
+                    inner,
+                    outer_key: block.map(|b| b ^ 0x5c),
+                }
+            }
+            Suite::Blake3 => State::Blake3(Box::new(blake3::Hasher::new_keyed(
+                &blake3::derive_key(context, key),
+            ))),
+        }))
+    }
+
+    pub fn hash(self, domain: Domain, data: &[u8]) -> Result<Vec<u8>> {
+        let mut h = self.hasher(domain)?;
+        h.update(data)?;
+        h.finish()
+    }
+
+    pub fn mac(self, domain: Domain, key: &[u8], data: &[u8]) -> Result<Vec<u8>> {
+        let mut h = self.keyed(domain, key)?;
+        h.update(data)?;
+        h.finish()
+    }
+
+    /// [`Suite::mac`] over data streamed from `source`.
+    pub fn mac_source<S: Source + ?Sized>(
+        self,
+        domain: Domain,
+        key: &[u8],
+        source: &S,
+    ) -> Result<Vec<u8>> {
+        let mut h = self.keyed(domain, key)?;
+        source.for_each_chunk(&mut |chunk| h.update(chunk))?;
+        h.finish()
+    }
+}
+
+// The domain is length-prefixed so that it cannot run into the data.
+fn update_domain(h: &mut Sha256, context: &str) {
+    h.update([context.len() as u8]);
+    h.update(context.as_bytes());
+}
+
+/// An incremental digest or MAC from a [`Suite`].
+pub struct Mac(State);
+
+enum State {
+    Keccak(Box<KeccakState>),
+    Sha256(Sha256),
+    HmacSha256 {
+        inner: Sha256,
+        outer_key: [u8; HMAC_BLOCK_BYTES],
+    },
+    Blake3(Box<blake3::Hasher>),
+}
+
+enum KeccakState {
+    Sha3(Sha3),
+    CShake(CShake),
+    Kmac(Kmac),
+}
+
+impl Mac {
+    pub fn update(&mut self, data: &[u8]) -> Result<()> {
+        match &mut self.0 {
+            State::Sha256(h) | State::HmacSha256 { inner: h, .. } => h.update(data),
+            State::Keccak(h) => match h.as_mut() {
+                KeccakState::Sha3(h) => h.update(data),
+                KeccakState::CShake(h) => h.update(data),
+                KeccakState::Kmac(h) => h.update(data),
+            },
+            State::Blake3(h) => {
+                h.update(data);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn finish(self) -> Result<Vec<u8>> {
+        let mut out = vec![0; 32];
+        match self.0 {
+            State::Sha256(h) => out.copy_from_slice(&h.finalize()),
+            State::Keccak(h) => match *h {
+                KeccakState::Sha3(h) => h.finalize(&mut out),
+                KeccakState::CShake(h) => h.finalize(&mut out),
+                KeccakState::Kmac(h) => h.finalize(&mut out),
+            },
+            State::HmacSha256 { inner, outer_key } => {
+                let mut outer = Sha256::new();
+                outer.update(outer_key);
+                outer.update(inner.finalize());
+                out.copy_from_slice(&outer.finalize());
+            }
+            State::Blake3(h) => out.copy_from_slice(h.finalize().as_bytes()),
+        }
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Pins every suite's output so that no change to the constructions goes unnoticed.
+    #[test]
+    fn known_answers() {
+        let vectors = [
+            (
+                Suite::Kmac256,
+                "f217161f6674cef8d593e3af04a73654b31e2485635d595832de611924f5c1c5",
+                "1d6d53324e2b052600d08ed4c962c1de99f9b4b5b22a8b63e457af17cae6ad93",
+            ),
+            (
+                Suite::HmacSha256,
+                "3b5af2c8b3599e3b1ad4851f2f89456aaf3a7086e67c3d8c4130b2922dfdce40",
+                "93d2957c1d37464fafdf0df44197c87384014e6fb5ee1985208a1159a2c4592f",
+            ),
+            (
+                Suite::Blake3,
+                "dfbd07332dcac308668d2904219d2810bec06b4155a3451b063c5afdc2b86995",
+                "fed33bedce23031f53ca9e7ed8e9d32a534c547bc572b2c50ac2be9504db4cc4",
+            ),
+        ];
+        for (suite, hash, mac) in vectors {
+            assert_eq!(
+                hex::encode(suite.hash(Domain::Transcript, b"data").unwrap()),
+                hash
+            );
+            assert_eq!(
+                hex::encode(suite.mac(Domain::RoundMac, b"key", b"data").unwrap()),
+                mac
+            );
+        }
+    }
+
+    #[test]
+    fn sha3_is_the_original_construction() {
+        assert_eq!(
+            hex::encode(sha3(b"").unwrap()),
+            "a7ffc6f8bf1ed76651c14756a061d662f580ff4de43b49fa82d80a4b80f8434a"
+        );
+        for domain in [Domain::RoundMac, Domain::Challenge] {
+            assert_eq!(
+                Suite::Sha3.hash(domain, b"data").unwrap(),
+                sha3(b"data").unwrap().to_vec()
+            );
+            assert_eq!(
+                Suite::Sha3.mac(domain, b"key", b"data").unwrap(),
+                sha3(b"keydata").unwrap().to_vec()
+            );
+        }
+    }
+
+    #[cfg(feature = "openssl")]
+    #[test]
+    fn hmac_sha256_matches_openssl() {
+        use openssl::{hash::MessageDigest, pkey::PKey, sign::Signer};
+
+        let context = Domain::RoundMac.context();
+        let data = [&[context.len() as u8], context.as_bytes(), b"data"].concat();
+        for key in [
+            &b"key"[..],
+            &[7; HMAC_BLOCK_BYTES],
+            &[7; HMAC_BLOCK_BYTES + 1],
+        ] {
+            let pkey = PKey::hmac(key).unwrap();
+            let mut signer = Signer::new(MessageDigest::sha256(), &pkey).unwrap();
+            signer.update(&data).unwrap();
+            assert_eq!(
+                Suite::HmacSha256
+                    .mac(Domain::RoundMac, key, b"data")
+                    .unwrap(),
+                signer.sign_to_vec().unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn blake3_matches_its_modes() {
+        let context = Domain::RoundMac.context();
+        assert_eq!(
+            Suite::Blake3.hash(Domain::RoundMac, b"data").unwrap(),
+            blake3::derive_key(context, b"data")
+        );
+        assert_eq!(
+            Suite::Blake3
+                .mac(Domain::RoundMac, b"key", b"data")
+                .unwrap(),
+            blake3::keyed_hash(&blake3::derive_key(context, b"key"), b"data").as_bytes()
+        );
+    }
+
+    #[test]
+    fn domains_and_keys_separate() {
+        for suite in Suite::ALL {
+            let mac = suite.mac(Domain::RoundMac, b"key", b"data").unwrap();
+            assert_eq!(mac.len(), 32);
+            assert_ne!(
+                suite.mac(Domain::RoundMac, b"other key", b"data").unwrap(),
+                mac
+            );
+            assert_ne!(suite.mac(Domain::RoundMac, b"key", b"other").unwrap(), mac);
+            if suite != Suite::Sha3 {
+                let domains = [
+                    Domain::RoundMac,
+                    Domain::DelayInput,
+                    Domain::Challenge,
+                    Domain::Transcript,
+                    Domain::ProofChallenge,
+                    Domain::Authenticator,
+                ];
+                let mut macs = domains.map(|d| suite.mac(d, b"key", b"data").unwrap());
+                let mut hashes = domains.map(|d| suite.hash(d, b"data").unwrap());
+                for digests in [&mut macs, &mut hashes] {
+                    digests.sort();
+                    assert!(digests.windows(2).all(|w| w[0] != w[1]));
+                }
+            }
+            assert_eq!(Suite::from_name(suite.name()).unwrap(), suite);
+
+            // Streaming and one-shot MACs agree.
+            let mut h = suite.keyed(Domain::RoundMac, b"key").unwrap();
+            h.update(b"da").unwrap();
+            h.update(b"ta").unwrap();
+            assert_eq!(h.finish().unwrap(), mac);
+        }
+        assert!(Suite::from_name("md5").is_err());
+    }
+}
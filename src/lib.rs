@@ -0,0 +1,43 @@
+//! Proof of Storage-Time, as described in *Proof of Storage-Time: Efficiently Checking Continuous Data Availability* (NDSS 2020).
+
+pub mod audit;
+pub mod backend;
+pub mod calibrate;
+#[cfg(feature = "openssl")]
+pub mod class_group;
+mod delay;
+mod encoding;
+mod error;
+mod hash;
+pub mod merkle;
+pub mod merkle_por;
+mod modulus;
+#[cfg(feature = "openssl")]
+mod mont;
+pub mod net;
+pub mod params;
+#[cfg(feature = "openssl")]
+pub mod por;
+mod post;
+pub mod posw;
+mod round;
+mod rsa;
+pub mod source;
+pub mod transcript;
+pub mod tree_mac;
+#[cfg(feature = "openssl")]
+pub mod vdf;
+mod verify;
+
+pub use delay::DelayFunction;
+pub use encoding::Encoding;
+pub use error::{PostError, Result};
+pub use hash::{Domain, Mac, Suite};
+pub use modulus::{Modulus, ModulusGenerator, Trapdoor};
+pub use params::{Params, PublicParams};
+pub use post::{ChainProof, Prove, Response, RoundProof, Store, Tag};
+pub use round::{FileMac, Round};
+pub use rsa::RsaSquaring;
+pub use source::Source;
+pub use transcript::Transcript;
+pub use verify::{verify, verify_chain, Rejection, Verdict, Verify};
@@ -1,107 +1,622 @@
-use std::time::Instant;
+use std::{
+    fs,
+    net::{SocketAddr, TcpListener},
+    path::{Path, PathBuf},
+    process::ExitCode,
+    time::{Duration, Instant},
+};
 
-use openssl::{
-    bn::{BigNum, BigNumContext},
-    hash::{DigestBytes, Hasher, MessageDigest},
-    rand::rand_bytes,
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use ndss::{
+    audit::Schedule,
+    backend::{self, BigIntBackend},
+    calibrate::Calibration,
+    net::{Audit, ProverDaemon, VerifierClient},
+    source::Reader,
+    verify_chain, ChainProof, DelayFunction, Encoding, FileMac, ModulusGenerator, Params,
+    PostError, Prove, PublicParams, Response, RsaSquaring, Source, Store, Suite, Tag, Transcript,
+    Trapdoor, Verify,
 };
 
-fn sha3(data: &[u8]) -> DigestBytes {
-    let mut h = Hasher::new(MessageDigest::sha3_256()).unwrap();
-    h.update(data).unwrap();
-    h.finish().unwrap()
+#[cfg(feature = "openssl")]
+use ndss::vdf::ProofKind;
+
+/// Proof of Storage-Time over an RSA squaring chain.
+///
+/// Keys, tags, responses, proofs and transcripts are written as JSON when the path ends in `.json` and in the binary encoding
+/// otherwise; either is accepted on input.
+#[derive(Parser)]
+#[command(version)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Generate a modulus with its trapdoor and fix the chain parameters.
+    Keygen(KeygenArgs),
+    /// Compute the verifier's tag for a file with the trapdoor.
+    Store(StoreArgs),
+    /// Compute the prover's response for a file, optionally with delay proofs.
+    Prove(ProveArgs),
+    /// Compare a response with a tag, or check a chain proof without the trapdoor.
+    Verify(VerifyArgs),
+    /// Find the first round at which two transcripts disagree.
+    Diff(DiffArgs),
+    /// Host files and answer audits on them over TCP.
+    Serve(ServeArgs),
+    /// Audit a prover daemon, once for each transcript written by `store`.
+    Audit(AuditArgs),
+    /// Time store, prove and verify over zero-filled buffers.
+    Bench(BenchArgs),
+}
+
+#[derive(Args)]
+struct KeygenArgs {
+    /// Modulus size in bits.
+    #[arg(long, default_value_t = 2048)]
+    bits: u32,
+    /// Draw safe primes.
+    #[arg(long)]
+    safe_primes: bool,
+    /// Draw safe primes and run the delay function in the squares modulo n.
+    #[arg(long)]
+    no_small_order: bool,
+    /// Number of delays in the chain.
+    #[arg(long, requires = "squarings", conflicts_with = "interval")]
+    rounds: Option<usize>,
+    /// Squarings per delay.
+    #[arg(long, requires = "rounds")]
+    squarings: Option<u64>,
+    /// Seconds between round responses; calibrates `--rounds` and `--squarings` on this machine.
+    #[arg(long, requires = "duration")]
+    interval: Option<u64>,
+    /// Seconds over which storage is proved.
+    #[arg(long)]
+    duration: Option<u64>,
+    /// How many times faster than this machine an adversary may square.
+    #[arg(long, default_value_t = 10.0)]
+    speedup: f64,
+    /// Hash function of the round MAC, the challenge derivation and the transcript digests.
+    #[arg(long, value_enum, default_value_t = SuiteArg::Kmac256)]
+    suite: SuiteArg,
+    /// Output for the public parameters.
+    #[arg(long)]
+    params: PathBuf,
+    /// Output for the secret trapdoor.
+    #[arg(long)]
+    trapdoor: PathBuf,
+}
+
+#[derive(Args)]
+struct StoreArgs {
+    #[arg(long)]
+    params: PathBuf,
+    #[arg(long)]
+    trapdoor: PathBuf,
+    /// The file to tag.
+    #[arg(long)]
+    file: PathBuf,
+    /// Memory-map the file instead of reading it (requires the `mmap` feature).
+    #[arg(long)]
+    mmap: bool,
+    /// 32-byte challenge in hex; a random one is drawn and printed if absent.
+    #[arg(long)]
+    challenge: Option<String>,
+    /// Output for the tag.
+    #[arg(long)]
+    tag: PathBuf,
+    /// Also write every round challenge and response, for locating a divergence later.
+    #[arg(long)]
+    transcript: Option<PathBuf>,
+    /// Keep the delay outputs in the transcript.
+    #[arg(long, requires = "transcript")]
+    delay_outputs: bool,
+}
+
+#[derive(Args)]
+struct ProveArgs {
+    #[arg(long)]
+    params: PathBuf,
+    /// The stored file.
+    #[arg(long)]
+    file: PathBuf,
+    /// Memory-map the file instead of reading it (requires the `mmap` feature).
+    #[arg(long)]
+    mmap: bool,
+    /// 32-byte challenge in hex.
+    #[arg(long)]
+    challenge: String,
+    /// Output for the response.
+    #[arg(long)]
+    response: PathBuf,
+    /// Also prove every delay, making the chain checkable without the trapdoor.
+    #[arg(long, value_enum, requires = "proof")]
+    proofs: Option<ProofArg>,
+    /// Output for the chain proof.
+    #[arg(long, requires = "proofs")]
+    proof: Option<PathBuf>,
+    /// Also write every round challenge and response, for locating a divergence later.
+    #[arg(long, conflicts_with = "proofs")]
+    transcript: Option<PathBuf>,
+    /// Keep the delay outputs in the transcript.
+    #[arg(long, requires = "transcript")]
+    delay_outputs: bool,
+}
+
+#[derive(Args)]
+struct VerifyArgs {
+    #[arg(long)]
+    response: PathBuf,
+    /// The verifier's tag.
+    #[arg(long, required_unless_present = "proof")]
+    tag: Option<PathBuf>,
+    /// A chain proof to check instead of a tag; the round responses themselves are not checked.
+    #[arg(long, requires_all = ["params", "challenge", "proofs"], conflicts_with = "tag")]
+    proof: Option<PathBuf>,
+    #[arg(long, conflicts_with = "tag")]
+    params: Option<PathBuf>,
+    /// 32-byte challenge in hex.
+    #[arg(long, conflicts_with = "tag")]
+    challenge: Option<String>,
+    #[arg(long, value_enum, conflicts_with = "tag")]
+    proofs: Option<ProofArg>,
+}
+
+#[derive(Args)]
+struct DiffArgs {
+    /// The verifier's transcript.
+    expected: PathBuf,
+    /// The prover's transcript.
+    actual: PathBuf,
+}
+
+#[derive(Args)]
+struct ServeArgs {
+    #[arg(long, default_value = "127.0.0.1:7878")]
+    listen: SocketAddr,
+    #[arg(long)]
+    params: PathBuf,
+    /// A file to host as `name=path`; may be repeated.
+    #[arg(long = "file", value_parser = parse_hosted, required = true)]
+    files: Vec<(String, PathBuf)>,
+}
+
+#[derive(Args)]
+struct AuditArgs {
+    #[arg(long)]
+    connect: SocketAddr,
+    #[arg(long)]
+    params: PathBuf,
+    /// The name under which the prover hosts the file.
+    #[arg(long)]
+    file: String,
+    /// A transcript from `store --transcript` for this file; may be repeated, one audit each.
+    #[arg(long = "transcript", required = true)]
+    transcripts: Vec<PathBuf>,
+    /// Rounds between checkpoints.
+    #[arg(long, default_value_t = 1)]
+    every: usize,
+    /// Seconds an honest prover takes per delay; measured on this machine if absent.
+    #[arg(long)]
+    round_time: Option<f64>,
+    /// Seconds of slack on every deadline, for the round function and the network.
+    #[arg(long, default_value_t = 1.0)]
+    slack: f64,
+    /// Seconds to keep waiting for a response past its deadline.
+    #[arg(long, default_value_t = 10.0)]
+    grace: f64,
+    /// Seconds between the starts of consecutive audits.
+    #[arg(long, default_value_t = 0.0)]
+    interval: f64,
+}
+
+#[derive(Args)]
+struct BenchArgs {
+    /// Modulus size in bits.
+    #[arg(long, default_value_t = 2048)]
+    bits: u32,
+    /// File sizes in MB.
+    #[arg(long, value_delimiter = ',', default_value = "64,128,192,256")]
+    sizes: Vec<usize>,
+    /// Number of delays in the chain.
+    #[arg(long, default_value_t = 720)]
+    rounds: usize,
+    /// Squarings per delay.
+    #[arg(long, default_value_t = 1 << 20)]
+    squarings: u64,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ProofArg {
+    Wesolowski,
+    Pietrzak,
+}
+
+#[cfg(feature = "openssl")]
+impl From<ProofArg> for ProofKind {
+    fn from(arg: ProofArg) -> Self {
+        match arg {
+            ProofArg::Wesolowski => ProofKind::Wesolowski,
+            ProofArg::Pietrzak => ProofKind::Pietrzak,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum SuiteArg {
+    Sha3,
+    Kmac256,
+    HmacSha256,
+    Blake3,
+}
+
+impl From<SuiteArg> for Suite {
+    fn from(arg: SuiteArg) -> Self {
+        match arg {
+            SuiteArg::Sha3 => Suite::Sha3,
+            SuiteArg::Kmac256 => Suite::Kmac256,
+            SuiteArg::HmacSha256 => Suite::HmacSha256,
+            SuiteArg::Blake3 => Suite::Blake3,
+        }
+    }
+}
+
+fn read<T: Encoding>(path: &Path) -> Result<T, PostError> {
+    let bytes = fs::read(path)?;
+    if bytes.starts_with(b"PoST") {
+        T::from_binary(&bytes)
+    } else {
+        T::from_json(&String::from_utf8_lossy(&bytes))
+    }
+}
+
+fn write<T: Encoding>(path: &Path, value: &T) -> Result<(), PostError> {
+    if path.extension().is_some_and(|e| e == "json") {
+        fs::write(path, value.to_json()?)?;
+    } else {
+        fs::write(path, value.to_binary()?)?;
+    }
+    Ok(())
+}
+
+fn open(path: &Path, mmap: bool) -> Result<Box<dyn Source>, PostError> {
+    if mmap {
+        #[cfg(feature = "mmap")]
+        return Ok(Box::new(ndss::source::map(path)?));
+        #[cfg(not(feature = "mmap"))]
+        return Err(PostError::Unsupported(
+            "memory maps without the mmap feature",
+        ));
+    }
+    Ok(Box::new(Reader::open(path)?))
+}
+
+fn parse_hosted(arg: &str) -> Result<(String, PathBuf), String> {
+    match arg.split_once('=') {
+        Some((name, path)) if !name.is_empty() => Ok((name.into(), path.into())),
+        _ => Err("expected name=path".into()),
+    }
+}
+
+fn seconds(secs: f64) -> Result<Duration, PostError> {
+    Duration::try_from_secs_f64(secs)
+        .map_err(|_| PostError::InvalidParameters(format!("{} seconds", secs)))
+}
+
+fn parse_challenge(hex: &str) -> Result<Vec<u8>, PostError> {
+    match hex::decode(hex) {
+        Ok(c) if c.len() == 32 => Ok(c),
+        _ => Err(PostError::InvalidParameters(
+            "the challenge must be 32 bytes of hex".into(),
+        )),
+    }
+}
+
+fn keygen(args: KeygenArgs) -> Result<(), PostError> {
+    let generator = if args.no_small_order {
+        ModulusGenerator::new(args.bits).no_small_order()
+    } else if args.safe_primes {
+        ModulusGenerator::new(args.bits).safe_primes()
+    } else {
+        ModulusGenerator::new(args.bits)
+    };
+    let (modulus, trapdoor) = generator.generate()?;
+    let params = match (args.rounds, args.squarings, args.interval, args.duration) {
+        (Some(k), Some(squarings), _, _) => Params::new(args.bits, k, squarings)?,
+        (_, _, Some(interval), Some(duration)) => {
+            let calibration = Calibration::measure(&modulus, Duration::from_secs(1))?;
+            println!("{:.0} squarings/s", calibration.squarings_per_sec);
+            calibration.schedule(
+                Duration::from_secs(interval),
+                Duration::from_secs(duration),
+                args.speedup,
+            )?
+        }
+        _ => {
+            return Err(PostError::InvalidParameters(
+                "either --rounds and --squarings or --interval and --duration are required".into(),
+            ))
+        }
+    }
+    .with_suite(args.suite.into());
+    println!(
+        "{} delays of {} squarings with a {}-bit modulus, hashing with {}",
+        params.k,
+        params.squarings,
+        params.n_bits,
+        params.suite.name()
+    );
+    write(&args.params, &PublicParams::new(modulus, params)?)?;
+    write(&args.trapdoor, &trapdoor)
+}
+
+fn store(args: StoreArgs) -> Result<(), PostError> {
+    let public: PublicParams = read(&args.params)?;
+    let trapdoor: Trapdoor = read(&args.trapdoor)?;
+    if trapdoor.modulus()? != public.modulus {
+        return Err(PostError::InvalidParameters(
+            "the trapdoor does not factor the modulus".into(),
+        ));
+    }
+    let c = match args.challenge {
+        Some(hex) => parse_challenge(&hex)?,
+        None => {
+            let mut c = vec![0; 32];
+            getrandom::getrandom(&mut c)?;
+            println!("challenge: {}", hex::encode(&c));
+            c
+        }
+    };
+    let file = open(&args.file, args.mmap)?;
+    let store = Store::new(
+        RsaSquaring::with_trapdoor(&trapdoor, public.params.squarings)?,
+        public.params.k,
+    )
+    .with_suite(public.params.suite);
+    let tag = match args.transcript {
+        Some(path) => {
+            let (tag, transcript) =
+                store.run_with_transcript(&c, &FileMac(&*file), args.delay_outputs)?;
+            write(&path, &transcript)?;
+            tag
+        }
+        None => store.run(&c, &FileMac(&*file))?,
+    };
+    write(&args.tag, &tag)
+}
+
+fn prove(args: ProveArgs) -> Result<(), PostError> {
+    let public: PublicParams = read(&args.params)?;
+    let c = parse_challenge(&args.challenge)?;
+    let file = open(&args.file, args.mmap)?;
+    let delay = RsaSquaring::new(&public.modulus, public.params.squarings)?;
+    let response = match (args.proofs, args.proof, args.transcript) {
+        (Some(kind), Some(path), _) => {
+            let (response, proof) = Prove::new(with_proofs(delay, kind)?, public.params.k)
+                .with_suite(public.params.suite)
+                .run_with_proofs(&c, &FileMac(&*file))?;
+            write(&path, &proof)?;
+            response
+        }
+        (_, _, Some(path)) => {
+            let (response, transcript) = Prove::new(delay, public.params.k)
+                .with_suite(public.params.suite)
+                .run_with_transcript(&c, &FileMac(&*file), args.delay_outputs)?;
+            write(&path, &transcript)?;
+            response
+        }
+        _ => Prove::new(delay, public.params.k)
+            .with_suite(public.params.suite)
+            .run(&c, &FileMac(&*file))?,
+    };
+    write(&args.response, &response)
+}
+
+#[cfg(feature = "openssl")]
+fn with_proofs(delay: RsaSquaring, kind: ProofArg) -> Result<RsaSquaring, PostError> {
+    Ok(delay.with_proofs(kind.into()))
 }
 
-// Unlike SHA-1 and SHA-2, Keccak does not have the length-extension weakness, hence does not need the HMAC nested construction. Instead, MAC computation can be performed by simply prepending the message with the key.
-fn hmac(key: &[u8], data: &[u8]) -> DigestBytes {
-    let mut h = Hasher::new(MessageDigest::sha3_256()).unwrap();
-    h.update(key).unwrap();
-    h.update(data).unwrap();
-    h.finish().unwrap()
+// The proof systems are built on OpenSSL.
+#[cfg(not(feature = "openssl"))]
+fn with_proofs(_: RsaSquaring, _: ProofArg) -> Result<RsaSquaring, PostError> {
+    Err(PostError::Unsupported(
+        "delay proofs without the openssl feature",
+    ))
 }
 
-fn setup(n_bits: i32) -> (BigNum, BigNum) {
-    let mut p = BigNum::new().unwrap();
-    p.generate_prime(n_bits >> 1, false, None, None).unwrap();
-    let mut q = BigNum::new().unwrap();
-    q.generate_prime(n_bits >> 1, false, None, None).unwrap();
-    (p, q)
+fn verify(args: VerifyArgs) -> Result<(), PostError> {
+    let response: Response = read(&args.response)?;
+    let verdict = match (
+        args.tag,
+        args.proof,
+        args.params,
+        args.challenge,
+        args.proofs,
+    ) {
+        (Some(tag), ..) => Verify::new(&read::<Tag>(&tag)?).run(&response),
+        (None, Some(proof), Some(params), Some(c), Some(kind)) => {
+            let public: PublicParams = read(&params)?;
+            let proof: ChainProof = read(&proof)?;
+            let delay = with_proofs(
+                RsaSquaring::new(&public.modulus, public.params.squarings)?,
+                kind,
+            )?;
+            verify_chain(
+                &delay,
+                &parse_challenge(&c)?,
+                public.params.k,
+                public.params.suite,
+                &response,
+                &proof,
+                |_, _| Ok(true),
+            )?
+        }
+        _ => unreachable!("enforced by the argument parser"),
+    };
+    println!("{:?}", verdict);
+    verdict.into_result()
 }
 
-fn eval_trap(x: &[u8], n: &BigNum, e: &BigNum, ctx: &mut BigNumContext) -> Vec<u8> {
-    let mut r = BigNum::new().unwrap();
-    r.mod_exp(&BigNum::from_slice(x).unwrap(), &e, n, ctx)
-        .unwrap();
-    r.to_vec()
+fn diff(args: DiffArgs) -> Result<(), PostError> {
+    let expected: Transcript = read(&args.expected)?;
+    let actual: Transcript = read(&args.actual)?;
+    let Some(i) = expected.first_divergence(&actual) else {
+        println!(
+            "the transcripts agree on all {} rounds",
+            expected.rounds.len()
+        );
+        return Ok(());
+    };
+    println!("first divergence at round {}", i);
+    for (name, t) in [("expected", &expected), ("actual", &actual)] {
+        match t.rounds.get(i) {
+            Some(r) => println!(
+                "{}: c = {}, v = {}",
+                name,
+                hex::encode(&r.c),
+                hex::encode(&r.v)
+            ),
+            None => println!("{}: no round {}", name, i),
+        }
+    }
+    Err(PostError::VerificationFailed)
 }
 
-fn eval(x: &[u8], n: &BigNum, t: usize) -> Vec<u8> {
-    let mut g = BigNum::from_slice(x).unwrap();
-    for _ in 0..(1 << t) {
-        g = &(&g * &g) % n;
+fn serve(args: ServeArgs) -> Result<(), PostError> {
+    let public: PublicParams = read(&args.params)?;
+    let listener = TcpListener::bind(args.listen)?;
+    let mut daemon = ProverDaemon::new();
+    for (name, path) in args.files {
+        println!("hosting {} as {}", path.display(), name);
+        daemon.host(name, path, read(&args.params)?);
     }
-    g.to_vec()
-}
-
-fn store(c: &[u8], d: &[u8], p: &BigNum, q: &BigNum, t: i32, k: i32) -> (Vec<u8>, Vec<u8>) {
-    let mut ctx = BigNumContext::new().unwrap();
-    let one = BigNum::from_u32(1).unwrap();
-
-    let n = p * q;
-    let phi = &(p - &one) * &(q - &one);
-    let mut e = BigNum::new().unwrap();
-    e.set_bit(1 << t).unwrap();
-    e = &e % &phi;
-
-    let mut c = c.to_vec();
-    let mut cs = vec![];
-    let mut vs = vec![];
-    for _ in 0..=k {
-        let v = hmac(&c, d);
-        cs.extend_from_slice(&c);
-        vs.extend_from_slice(&v);
-        c = sha3(&eval_trap(&sha3(&v), &n, &e, &mut ctx)).to_vec();
+    println!(
+        "listening on {} for chains of {} delays",
+        listener.local_addr()?,
+        public.params.k
+    );
+    daemon.serve(listener)
+}
+
+fn audit(args: AuditArgs) -> Result<(), PostError> {
+    let public: PublicParams = read(&args.params)?;
+    let round_time = match args.round_time {
+        Some(secs) => seconds(secs)?,
+        None => Calibration::measure(&public.modulus, Duration::from_secs(1))?
+            .round_time(public.params.squarings),
+    };
+    let schedule = Schedule::new(args.every, round_time, seconds(args.slack)?)?;
+    let audits = args
+        .transcripts
+        .iter()
+        .map(|path| {
+            Ok(Audit {
+                file: args.file.clone(),
+                expected: read(path)?,
+            })
+        })
+        .collect::<Result<Vec<_>, PostError>>()?;
+    let mut client = VerifierClient::new(args.connect, schedule, seconds(args.grace)?);
+    client.run(audits, seconds(args.interval)?)?;
+    let mut passed = true;
+    for record in client.records() {
+        passed &= record.passed();
+        match record.verdict {
+            Some(verdict) => println!("{}: {:?}", record.file, verdict),
+            None => println!("{}: no response", record.file),
+        }
+        for finding in &record.findings {
+            println!("  {:?}", finding);
+        }
     }
-    (sha3(&cs).to_vec(), sha3(&vs).to_vec())
-}
-
-fn prove(c: &[u8], d: &[u8], n: &BigNum, t: usize, k: i32) -> (Vec<u8>, Vec<u8>) {
-    let mut c = c.to_vec();
-    let mut cs = vec![];
-    let mut vs = vec![];
-    for _ in 0..=k {
-        let v = hmac(&c, d);
-        cs.extend_from_slice(&c);
-        vs.extend_from_slice(&v);
-        c = sha3(&eval(&sha3(&v), n, t)).to_vec();
+    if passed {
+        Ok(())
+    } else {
+        Err(PostError::VerificationFailed)
     }
-    (sha3(&cs).to_vec(), sha3(&vs).to_vec())
 }
 
-fn main() {
-    const T: usize = 28;
-    const N_BITS: i32 = 2048;
+fn throughput<B: BigIntBackend>(
+    name: &str,
+    trapdoor: &Trapdoor,
+    squarings: u64,
+) -> Result<(), PostError> {
+    let delay = RsaSquaring::<B>::with_trapdoor_in(trapdoor, squarings)?;
+    let now = Instant::now();
+    delay.eval(&[0; 32])?;
+    println!("{}: {} squarings in {:.3?}", name, squarings, now.elapsed());
+    Ok(())
+}
+
+fn bench(args: BenchArgs) -> Result<(), PostError> {
+    let Params { k, squarings, .. } = Params::new(args.bits, args.rounds, args.squarings)?;
+    let (modulus, trapdoor) = ModulusGenerator::new(args.bits).safe_primes().generate()?;
+    #[cfg(feature = "openssl")]
+    throughput::<backend::OpenSsl>("openssl", &trapdoor, squarings)?;
+    #[cfg(feature = "gmp")]
+    throughput::<backend::Gmp>("gmp", &trapdoor, squarings)?;
+    #[cfg(feature = "num-bigint")]
+    throughput::<backend::NumBigint>("num-bigint", &trapdoor, squarings)?;
+
+    for size in args.sizes {
+        println!("{} delay(s) of {} squarings, {} MB", k, squarings, size);
 
-    for k in 1..5 {
-        for size in [64, 128, 192, 256] {
-            println!("{} month(s), {} MB", k, size);
+        let mut c = [0; 32];
+        getrandom::getrandom(&mut c)?;
 
-            let mut c = [0; 32];
-            rand_bytes(&mut c).unwrap();
+        let file = vec![0; size * 1024 * 1024];
 
-            let file = vec![0; size * 1024 * 1024];
+        let now = Instant::now();
+        let a = Store::new(RsaSquaring::with_trapdoor(&trapdoor, squarings)?, k)
+            .run(&c, &FileMac(&file))?;
+        println!("store: {:.3?}", now.elapsed());
 
-            let (p, q) = setup(N_BITS);
+        let now = Instant::now();
+        let b = Prove::new(RsaSquaring::new(&modulus, squarings)?, k).run(&c, &FileMac(&file))?;
+        println!("prove: {:.3?}", now.elapsed());
+
+        let verdict = Verify::new(&a).run(&b);
+        println!("verify: {:?}", verdict);
+        verdict.into_result()?;
+
+        #[cfg(feature = "openssl")]
+        for kind in [ProofKind::Wesolowski, ProofKind::Pietrzak] {
+            let delay = RsaSquaring::new(&modulus, squarings)?.with_proofs(kind);
 
             let now = Instant::now();
-            let a = store(&c, &file, &p, &q, T as i32, k * 720);
-            println!("store: {:.3?}", now.elapsed());
+            let (b, proof) = Prove::new(&delay, k).run_with_proofs(&c, &FileMac(&file))?;
+            println!("prove ({:?}): {:.3?}", kind, now.elapsed());
 
+            let size: usize = proof.rounds.iter().map(|r| r.proof.len()).sum();
             let now = Instant::now();
-            let b = prove(&c, &file, &(&p * &q), T, k * 720);
-            println!("prove: {:.3?}", now.elapsed());
+            let verdict =
+                verify_chain(&delay, &c, k, Suite::default(), &b, &proof, |_, _| Ok(true))?;
+            println!("verify ({:?}, {} bytes): {:.3?}", kind, size, now.elapsed());
+            verdict.into_result()?;
+        }
+    }
+    Ok(())
+}
 
-            assert_eq!(a, b);
+fn main() -> ExitCode {
+    let result = match Cli::parse().command {
+        Command::Keygen(args) => keygen(args),
+        Command::Store(args) => store(args),
+        Command::Prove(args) => prove(args),
+        Command::Verify(args) => verify(args),
+        Command::Diff(args) => diff(args),
+        Command::Serve(args) => serve(args),
+        Command::Audit(args) => audit(args),
+        Command::Bench(args) => bench(args),
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::FAILURE
         }
     }
 }
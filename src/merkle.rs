@@ -0,0 +1,115 @@
+use crate::{error::Result, hash::sha3};
+
+const LEAF: u8 = 0;
+const NODE: u8 = 1;
+
+pub fn hash_leaf(data: &[u8]) -> Result<Vec<u8>> {
+    Ok(sha3(&[&[LEAF], data].concat())?.to_vec())
+}
+
+pub fn hash_node(left: &[u8], right: &[u8]) -> Result<Vec<u8>> {
+    Ok(sha3(&[&[NODE], left, right].concat())?.to_vec())
+}
+
+/// A SHA3 Merkle tree; the leaf level is padded to a power of two with empty leaves.
+pub struct MerkleTree {
+    // levels[0] are the leaf hashes, the last level is the root.
+    levels: Vec<Vec<Vec<u8>>>,
+    leaves: usize,
+}
+
+impl MerkleTree {
+    pub fn new(leaves: Vec<Vec<u8>>) -> Result<Self> {
+        let count = leaves.len();
+        let mut level = leaves;
+        let empty = hash_leaf(&[])?;
+        level.resize(count.max(1).next_power_of_two(), empty);
+        let mut levels = vec![level];
+        while levels.last().unwrap().len() > 1 {
+            let next = levels
+                .last()
+                .unwrap()
+                .chunks(2)
+                .map(|pair| hash_node(&pair[0], &pair[1]))
+                .collect::<Result<_>>()?;
+            levels.push(next);
+        }
+        Ok(Self {
+            levels,
+            leaves: count,
+        })
+    }
+
+    pub fn root(&self) -> &[u8] {
+        &self.levels.last().unwrap()[0]
+    }
+
+    pub fn leaves(&self) -> usize {
+        self.leaves
+    }
+
+    pub fn depth(&self) -> usize {
+        self.levels.len() - 1
+    }
+
+    /// The sibling hashes from the leaf up to (excluding) the root.
+    pub fn path(&self, mut i: usize) -> Vec<&[u8]> {
+        self.levels[..self.depth()]
+            .iter()
+            .map(|level| {
+                let sibling = &level[i ^ 1][..];
+                i >>= 1;
+                sibling
+            })
+            .collect()
+    }
+}
+
+/// Recomputes the root from leaf `i` and its authentication path.
+pub fn verify_path(root: &[u8], mut i: usize, leaf: &[u8], path: &[&[u8]]) -> Result<bool> {
+    let mut h = leaf.to_vec();
+    for sibling in path {
+        h = if i & 1 == 0 {
+            hash_node(&h, sibling)?
+        } else {
+            hash_node(sibling, &h)?
+        };
+        i >>= 1;
+    }
+    Ok(i == 0 && h == root)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn paths() {
+        for count in [1, 2, 3, 5, 8] {
+            let leaves = (0..count)
+                .map(|i| hash_leaf(&[i as u8]).unwrap())
+                .collect::<Vec<_>>();
+            let tree = MerkleTree::new(leaves.clone()).unwrap();
+            assert_eq!(tree.leaves(), count);
+            assert_eq!(
+                tree.depth(),
+                count.next_power_of_two().trailing_zeros() as usize
+            );
+            for (i, leaf) in leaves.iter().enumerate() {
+                let path = tree.path(i);
+                assert!(verify_path(tree.root(), i, leaf, &path).unwrap());
+                assert!(!verify_path(tree.root(), i ^ 1, leaf, &path).unwrap());
+                assert!(
+                    !verify_path(tree.root(), i, &hash_leaf(b"other").unwrap(), &path).unwrap()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_out_of_range_leaves() {
+        let leaves = (0..4).map(|i| hash_leaf(&[i]).unwrap()).collect::<Vec<_>>();
+        let tree = MerkleTree::new(leaves.clone()).unwrap();
+        assert!(!verify_path(tree.root(), 4, &leaves[0], &tree.path(0)).unwrap());
+    }
+}
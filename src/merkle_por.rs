@@ -0,0 +1,250 @@
+//! Sampled proof of retrievability over a Merkle commitment.
+//!
+//! At store time the file is cut into fixed-size blocks and committed to with a [`MerkleTree`]. Each round challenge
+//! is expanded into `l` leaf indices, and the round response consists of those blocks with their authentication paths.
+//! If a fraction `f` of the blocks is lost, a single round goes undetected with probability `(1 - f)^l`.
+
+use crate::{
+    error::{PostError, Result},
+    hash::Suite,
+    merkle::{hash_leaf, verify_path, MerkleTree},
+    round::{sample, Round},
+    source::Source,
+};
+
+const HASH_BYTES: usize = 32;
+
+fn block<S: Source + ?Sized>(d: &S, block_size: usize, i: usize) -> Result<Vec<u8>> {
+    d.read_padded((i * block_size) as u64, block_size)
+}
+
+/// Expands a round challenge into `l` leaf indices with the chain's `suite`.
+pub fn challenge(suite: Suite, c: &[u8], blocks: usize, l: usize) -> Result<Vec<usize>> {
+    sample(suite, c, b"merkle-index", blocks, l)
+}
+
+/// Probability that a round sampling `l` blocks hits at least one of a `corrupted` fraction of blocks.
+pub fn detection_probability(corrupted: f64, l: usize) -> f64 {
+    1.0 - (1.0 - corrupted).powi(l as i32)
+}
+
+/// Smallest number of samples per round that detects a `corrupted` fraction with at least probability `target`.
+///
+/// Both must lie strictly between 0 and 1.
+pub fn samples_for(corrupted: f64, target: f64) -> Result<usize> {
+    let open = |x: f64| x > 0.0 && x < 1.0;
+    if !open(corrupted) || !open(target) {
+        return Err(PostError::InvalidParameters(format!(
+            "corrupted fraction {} and detection probability {} must lie in (0, 1)",
+            corrupted, target
+        )));
+    }
+    let l = ((1.0 - target).ln() / (1.0 - corrupted).ln()).ceil();
+    // A fraction that rounds `1 - corrupted` to 1 would need unboundedly many samples, and `detection_probability`
+    // takes the count as an `i32` exponent.
+    if !l.is_finite() || l > i32::MAX as f64 {
+        return Err(PostError::InvalidParameters(format!(
+            "cannot detect a corrupted fraction of {}",
+            corrupted
+        )));
+    }
+    Ok(l as usize)
+}
+
+/// What the verifier keeps after committing to the file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MerkleCommitment {
+    pub root: Vec<u8>,
+    pub blocks: usize,
+    pub block_size: usize,
+}
+
+impl MerkleCommitment {
+    /// Spot-checks a single round response to the challenge `c` of a chain hashed with `suite`.
+    pub fn check(&self, suite: Suite, c: &[u8], l: usize, response: &[u8]) -> Result<bool> {
+        if self.blocks == 0 {
+            return Err(PostError::MalformedProof(
+                "commitment to an empty file".into(),
+            ));
+        }
+        let depth = self.blocks.next_power_of_two().trailing_zeros() as usize;
+        let opening = self.block_size + depth * HASH_BYTES;
+        if response.len() != l * opening {
+            return Err(PostError::MalformedProof(
+                "unexpected Merkle PoR response length".into(),
+            ));
+        }
+        for (i, o) in challenge(suite, c, self.blocks, l)?
+            .into_iter()
+            .zip(response.chunks(opening))
+        {
+            let (b, path) = o.split_at(self.block_size);
+            let path = path.chunks(HASH_BYTES).collect::<Vec<_>>();
+            if !verify_path(&self.root, i, &hash_leaf(b)?, &path)? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+}
+
+/// Round function opening `l` sampled blocks of the file per challenge.
+pub struct MerkleRound<'a, S: ?Sized = [u8]> {
+    d: &'a S,
+    tree: MerkleTree,
+    block_size: usize,
+    l: usize,
+}
+
+impl<'a, S: Source + ?Sized> MerkleRound<'a, S> {
+    /// Reads the file once to hash its blocks; only the tree is kept in memory.
+    pub fn new(d: &'a S, block_size: usize, l: usize) -> Result<Self> {
+        if block_size == 0 || l == 0 {
+            return Err(PostError::InvalidParameters(
+                "block size and samples must be positive".into(),
+            ));
+        }
+        if d.size()? == 0 {
+            return Err(PostError::InvalidParameters(
+                "cannot commit to an empty file".into(),
+            ));
+        }
+        let leaves = (0..d.size()?.div_ceil(block_size as u64) as usize)
+            .map(|i| hash_leaf(&block(d, block_size, i)?))
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            d,
+            tree: MerkleTree::new(leaves)?,
+            block_size,
+            l,
+        })
+    }
+
+    pub fn commitment(&self) -> MerkleCommitment {
+        MerkleCommitment {
+            root: self.tree.root().to_vec(),
+            blocks: self.tree.leaves(),
+            block_size: self.block_size,
+        }
+    }
+}
+
+impl<S: Source + ?Sized> Round for MerkleRound<'_, S> {
+    fn respond(&self, suite: Suite, c: &[u8]) -> Result<Vec<u8>> {
+        let mut v = vec![];
+        for i in challenge(suite, c, self.tree.leaves(), self.l)? {
+            v.extend_from_slice(&block(self.d, self.block_size, i)?);
+            for sibling in self.tree.path(i) {
+                v.extend_from_slice(sibling);
+            }
+        }
+        Ok(v)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BLOCK_SIZE: usize = 64;
+    const L: usize = 5;
+
+    fn file() -> Vec<u8> {
+        (0..1000).map(|i| (i * 7) as u8).collect()
+    }
+
+    #[test]
+    fn roundtrip() {
+        let d = file();
+        let round = MerkleRound::new(&d[..], BLOCK_SIZE, L).unwrap();
+        let commitment = round.commitment();
+        assert_eq!(commitment.blocks, d.len().div_ceil(BLOCK_SIZE));
+        for c in [&b"c"[..], b"another challenge"] {
+            let v = round.respond(Suite::default(), c).unwrap();
+            assert!(commitment.check(Suite::default(), c, L, &v).unwrap());
+            assert!(!commitment.check(Suite::default(), b"other", L, &v).unwrap());
+        }
+    }
+
+    #[test]
+    fn suites() {
+        let d = file();
+        let round = MerkleRound::new(&d[..], BLOCK_SIZE, L).unwrap();
+        let commitment = round.commitment();
+        for suite in Suite::ALL {
+            let v = round.respond(suite, b"c").unwrap();
+            assert!(commitment.check(suite, b"c", L, &v).unwrap());
+        }
+        let v = round.respond(Suite::Kmac256, b"c").unwrap();
+        assert!(!commitment.check(Suite::Blake3, b"c", L, &v).unwrap());
+    }
+
+    #[test]
+    fn rejects_tampering() {
+        let d = file();
+        let round = MerkleRound::new(&d[..], BLOCK_SIZE, L).unwrap();
+        let commitment = round.commitment();
+        let v = round.respond(Suite::default(), b"c").unwrap();
+        for i in [0, BLOCK_SIZE, v.len() - 1] {
+            let mut forged = v.clone();
+            forged[i] ^= 1;
+            assert!(!commitment
+                .check(Suite::default(), b"c", L, &forged)
+                .unwrap());
+        }
+        assert!(commitment
+            .check(Suite::default(), b"c", L, &v[1..])
+            .is_err());
+
+        let mut corrupted = d.clone();
+        for b in &mut corrupted {
+            *b ^= 1;
+        }
+        let v = MerkleRound::new(&corrupted[..], BLOCK_SIZE, L)
+            .unwrap()
+            .respond(Suite::default(), b"c")
+            .unwrap();
+        assert!(!commitment.check(Suite::default(), b"c", L, &v).unwrap());
+    }
+
+    #[test]
+    fn sample_sizes() {
+        let l = samples_for(0.01, 0.99).unwrap();
+        assert!(detection_probability(0.01, l) >= 0.99);
+        assert!(detection_probability(0.01, l - 1) < 0.99);
+        assert_eq!(samples_for(0.5, 0.5).unwrap(), 1);
+
+        for (corrupted, target) in [
+            (0.0, 0.99),
+            (1.0, 0.99),
+            (-0.1, 0.99),
+            (0.01, 0.0),
+            (0.01, 1.0),
+            (0.01, 1.5),
+            (f64::NAN, 0.99),
+            (1e-300, 0.99),
+        ] {
+            assert!(matches!(
+                samples_for(corrupted, target),
+                Err(PostError::InvalidParameters(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn rejects_empty_commitments() {
+        let commitment = MerkleCommitment {
+            root: vec![0; HASH_BYTES],
+            blocks: 0,
+            block_size: 4,
+        };
+        assert!(matches!(
+            commitment.check(Suite::default(), b"c", 1, &[0; 4]),
+            Err(PostError::MalformedProof(_))
+        ));
+        assert!(matches!(
+            MerkleRound::new(b"", 4, 1),
+            Err(PostError::InvalidParameters(_))
+        ));
+    }
+}
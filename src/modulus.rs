@@ -0,0 +1,357 @@
+#[cfg(feature = "openssl")]
+use openssl::bn::BigNum;
+
+#[cfg(feature = "openssl")]
+use crate::backend::OpenSsl;
+use crate::{
+    backend::{BigIntBackend, DefaultBackend},
+    error::{PostError, Result},
+    params::check_modulus_bits,
+};
+
+// FIPS 186-4, B.3.1: |p - q| > 2^(n_bits / 2 - 100), so that n cannot be factored with Fermat's method.
+const FERMAT_MARGIN_BITS: u32 = 100;
+
+/// A public RSA modulus `n = pq`.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Modulus {
+    // Big-endian without leading zeros.
+    n: Vec<u8>,
+    no_small_order: bool,
+}
+
+impl Modulus {
+    /// Checks the size of the big-endian `n` against [`Params`](crate::Params) and that it is odd.
+    pub fn from_bytes(n: &[u8]) -> Result<Self> {
+        let n = trim(n);
+        check_modulus_bits(bits(n))?;
+        if n[n.len() - 1] & 1 == 0 {
+            return Err(PostError::InvalidParameters("even modulus".into()));
+        }
+        Ok(Self {
+            n: n.to_vec(),
+            no_small_order: false,
+        })
+    }
+
+    /// [`Modulus::from_bytes`] for an OpenSSL integer.
+    #[cfg(feature = "openssl")]
+    pub fn new(n: BigNum) -> Result<Self> {
+        Self::from_bytes(&n.to_vec())
+    }
+
+    /// Marks a modulus generated with [`ModulusGenerator::no_small_order`], for a verifier that only knows `n`.
+    pub fn with_no_small_order(self) -> Self {
+        Self {
+            no_small_order: true,
+            ..self
+        }
+    }
+
+    /// `n` in big-endian without leading zeros.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.n
+    }
+
+    #[cfg(feature = "openssl")]
+    pub fn n(&self) -> Result<BigNum> {
+        Ok(BigNum::from_slice(&self.n)?)
+    }
+
+    pub fn bits(&self) -> u32 {
+        bits(&self.n)
+    }
+
+    /// Whether the delay function runs in the squares `QR_n`, see [`ModulusGenerator::no_small_order`].
+    pub fn no_small_order(&self) -> bool {
+        self.no_small_order
+    }
+}
+
+/// The verifier's secret factorization of the RSA modulus.
+pub struct Trapdoor {
+    // Big-endian without leading zeros, like `n = pq`.
+    pub(crate) p: Vec<u8>,
+    pub(crate) q: Vec<u8>,
+    n: Vec<u8>,
+    no_small_order: bool,
+}
+
+impl Trapdoor {
+    /// Checks that the big-endian `p` and `q` are distinct primes of balanced size, far enough apart to resist Fermat
+    /// factoring, whose product has a supported size.
+    pub fn from_bytes(p: &[u8], q: &[u8]) -> Result<Self> {
+        Self::from_bytes_in::<DefaultBackend>(p, q)
+    }
+
+    /// [`Trapdoor::from_bytes`] with the arithmetic and primality tests of backend `B`.
+    pub fn from_bytes_in<B: BigIntBackend>(p: &[u8], q: &[u8]) -> Result<Self> {
+        let (p, q) = (trim(p), trim(q));
+        let (p_int, q_int) = (B::from_bytes(p)?, B::from_bytes(q)?);
+        let n = B::to_bytes(&B::mul(&p_int, &q_int)?)?;
+        check_modulus_bits(bits(&n))?;
+        if !B::is_prime(&p_int)? || !B::is_prime(&q_int)? {
+            return Err(PostError::InvalidParameters("factor is not prime".into()));
+        }
+        if bits(p).abs_diff(bits(q)) > 1 {
+            return Err(PostError::InvalidParameters(format!(
+                "unbalanced factors of {} and {} bits",
+                bits(p),
+                bits(q)
+            )));
+        }
+        let d = if (p.len(), p) >= (q.len(), q) {
+            B::sub(&p_int, &q_int)?
+        } else {
+            B::sub(&q_int, &p_int)?
+        };
+        if bits(&B::to_bytes(&d)?) <= (bits(&n) / 2).saturating_sub(FERMAT_MARGIN_BITS) {
+            return Err(PostError::InvalidParameters(
+                "factors are equal or too close".into(),
+            ));
+        }
+        Ok(Self {
+            p: p.to_vec(),
+            q: q.to_vec(),
+            n,
+            no_small_order: false,
+        })
+    }
+
+    /// [`Trapdoor::from_bytes`] for OpenSSL integers.
+    #[cfg(feature = "openssl")]
+    pub fn from_primes(p: BigNum, q: BigNum) -> Result<Self> {
+        Self::from_bytes_in::<OpenSsl>(&p.to_vec(), &q.to_vec())
+    }
+
+    /// Runs the delay function in `QR_n`, after checking that both factors are safe primes.
+    pub fn with_no_small_order(self) -> Result<Self> {
+        if !is_safe_prime::<DefaultBackend>(&self.p)? || !is_safe_prime::<DefaultBackend>(&self.q)?
+        {
+            return Err(PostError::InvalidParameters(
+                "factor is not a safe prime".into(),
+            ));
+        }
+        Ok(Self {
+            no_small_order: true,
+            ..self
+        })
+    }
+
+    pub fn no_small_order(&self) -> bool {
+        self.no_small_order
+    }
+
+    pub fn modulus(&self) -> Result<Modulus> {
+        Ok(Modulus {
+            n: self.n.clone(),
+            no_small_order: self.no_small_order,
+        })
+    }
+}
+
+fn trim(a: &[u8]) -> &[u8] {
+    &a[a.iter().take_while(|&&b| b == 0).count()..]
+}
+
+// The bit length of a big-endian magnitude.
+fn bits(a: &[u8]) -> u32 {
+    let a = trim(a);
+    match a.first() {
+        Some(b) => a.len() as u32 * 8 - b.leading_zeros(),
+        None => 0,
+    }
+}
+
+// p prime and (p - 1) / 2 prime
+fn is_safe_prime<B: BigIntBackend>(p: &[u8]) -> Result<bool> {
+    let mut carry = 0;
+    let half = p
+        .iter()
+        .map(|&b| {
+            let h = carry | b >> 1;
+            carry = b << 7;
+            h
+        })
+        .collect::<Vec<_>>();
+    Ok(B::is_prime(&B::from_bytes(p)?)? && B::is_prime(&B::from_bytes(&half)?)?)
+}
+
+/// Generates RSA moduli of exactly `n_bits` bits together with their [`Trapdoor`].
+#[derive(Clone, Copy, Debug)]
+pub struct ModulusGenerator {
+    n_bits: u32,
+    safe_primes: bool,
+    no_small_order: bool,
+}
+
+impl ModulusGenerator {
+    pub fn new(n_bits: u32) -> Self {
+        Self {
+            n_bits,
+            safe_primes: false,
+            no_small_order: false,
+        }
+    }
+
+    /// Uses safe primes `p = 2p' + 1` and `q = 2q' + 1`, so that `φ(n) / 4 = p'q'` has no small factors.
+    pub fn safe_primes(self) -> Self {
+        Self {
+            safe_primes: true,
+            ..self
+        }
+    }
+
+    /// Uses safe primes and runs the delay function in the squares `QR_n`: a cyclic group of order `p'q'`, so apart
+    /// from the identity it has no elements of order below `min(p', q')`. Inputs are squared once on the way in.
+    pub fn no_small_order(self) -> Self {
+        Self {
+            safe_primes: true,
+            no_small_order: true,
+            ..self
+        }
+    }
+
+    pub fn generate(&self) -> Result<(Modulus, Trapdoor)> {
+        self.generate_in::<DefaultBackend>()
+    }
+
+    /// Generates the primes with backend `B`, retrying until they pass [`Trapdoor::from_bytes_in`].
+    pub fn generate_in<B: BigIntBackend>(&self) -> Result<(Modulus, Trapdoor)> {
+        check_modulus_bits(self.n_bits)?;
+        loop {
+            let p = self.prime::<B>(self.n_bits.div_ceil(2))?;
+            let q = self.prime::<B>(self.n_bits / 2)?;
+            let trapdoor = match Trapdoor::from_bytes_in::<B>(&p, &q) {
+                Ok(trapdoor) if trapdoor.modulus()?.bits() == self.n_bits => trapdoor,
+                Ok(_) | Err(PostError::InvalidParameters(_)) => continue,
+                Err(e) => return Err(e),
+            };
+            let trapdoor = if self.no_small_order {
+                trapdoor.with_no_small_order()?
+            } else {
+                trapdoor
+            };
+            return Ok((trapdoor.modulus()?, trapdoor));
+        }
+    }
+
+    fn prime<B: BigIntBackend>(&self, bits: u32) -> Result<Vec<u8>> {
+        let p = if self.safe_primes {
+            B::generate_safe_prime(bits)?
+        } else {
+            B::generate_prime(bits)?
+        };
+        B::to_bytes(&p)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type B = DefaultBackend;
+
+    fn prime(bits: u32, safe: bool) -> Vec<u8> {
+        let p = if safe {
+            B::generate_safe_prime(bits)
+        } else {
+            B::generate_prime(bits)
+        };
+        B::to_bytes(&p.unwrap()).unwrap()
+    }
+
+    fn int(a: &[u8]) -> <B as BigIntBackend>::Int {
+        B::from_bytes(a).unwrap()
+    }
+
+    fn rejects(p: &[u8], q: &[u8]) -> bool {
+        matches!(
+            Trapdoor::from_bytes(p, q),
+            Err(PostError::InvalidParameters(_))
+        )
+    }
+
+    #[test]
+    fn from_bytes() {
+        // Not every backend sets the second highest bit, so only 513 and 512 bits always multiply to a valid size.
+        let p = prime(513, false);
+        let q = prime(512, false);
+        let trapdoor = Trapdoor::from_bytes(&p, &q).unwrap();
+        let n = B::to_bytes(&B::mul(&int(&p), &int(&q)).unwrap()).unwrap();
+        assert_eq!(trapdoor.modulus().unwrap().as_bytes(), &n[..]);
+        assert_eq!(
+            Trapdoor::from_bytes(&[&[0, 0][..], &p].concat(), &q)
+                .unwrap()
+                .modulus()
+                .unwrap(),
+            Modulus::from_bytes(&[&[0][..], &n].concat()).unwrap()
+        );
+        #[cfg(feature = "openssl")]
+        assert_eq!(
+            Trapdoor::from_primes(
+                BigNum::from_slice(&p).unwrap(),
+                BigNum::from_slice(&q).unwrap()
+            )
+            .unwrap()
+            .modulus()
+            .unwrap()
+            .n()
+            .unwrap(),
+            BigNum::from_slice(&n).unwrap()
+        );
+
+        assert!(rejects(&p, &p));
+        assert!(rejects(&prime(400, false), &prime(624, false)));
+        assert!(rejects(&prime(256, false), &prime(256, false)));
+        let one = B::from_u64(1).unwrap();
+        let composite = B::to_bytes(&B::add(&int(&p), &one).unwrap()).unwrap();
+        assert!(rejects(&composite, &q));
+
+        // The next prime after p is far too close to it.
+        let two = B::from_u64(2).unwrap();
+        let mut close = int(&p);
+        loop {
+            close = B::add(&close, &two).unwrap();
+            if B::is_prime(&close).unwrap() {
+                break;
+            }
+        }
+        assert!(rejects(&p, &B::to_bytes(&close).unwrap()));
+
+        let even = B::to_bytes(&B::add(&int(&n), &one).unwrap()).unwrap();
+        assert!(Modulus::from_bytes(&even).is_err());
+        assert!(Modulus::from_bytes(&[]).is_err());
+    }
+
+    #[test]
+    fn no_small_order() {
+        let trapdoor = Trapdoor::from_bytes(&prime(513, false), &prime(512, false)).unwrap();
+        assert!(matches!(
+            trapdoor.with_no_small_order(),
+            Err(PostError::InvalidParameters(_))
+        ));
+        let trapdoor = Trapdoor::from_bytes(&prime(513, true), &prime(512, true))
+            .unwrap()
+            .with_no_small_order()
+            .unwrap();
+        assert!(trapdoor.modulus().unwrap().no_small_order());
+    }
+
+    #[test]
+    fn generator() {
+        for bits in [1024, 1025] {
+            let (modulus, trapdoor) = ModulusGenerator::new(bits).generate().unwrap();
+            assert_eq!(modulus.bits(), bits);
+            assert_eq!(trapdoor.modulus().unwrap(), modulus);
+        }
+        let (_, trapdoor) = ModulusGenerator::new(1024)
+            .safe_primes()
+            .generate()
+            .unwrap();
+        assert!(
+            is_safe_prime::<B>(&trapdoor.p).unwrap() && is_safe_prime::<B>(&trapdoor.q).unwrap()
+        );
+        assert!(ModulusGenerator::new(512).generate().is_err());
+    }
+}
@@ -0,0 +1,145 @@
+//! Montgomery arithmetic through OpenSSL's `BN_MONT_CTX`, which the `openssl` crate does not wrap.
+
+use std::os::raw::c_int;
+
+use foreign_types::{ForeignType, ForeignTypeRef};
+use openssl::{
+    bn::{BigNum, BigNumContextRef, BigNumRef},
+    error::ErrorStack,
+};
+use openssl_sys::{BIGNUM, BN_CTX, BN_MONT_CTX};
+
+use crate::error::Result;
+
+extern "C" {
+    fn BN_MONT_CTX_new() -> *mut BN_MONT_CTX;
+    fn BN_MONT_CTX_free(mont: *mut BN_MONT_CTX);
+    fn BN_MONT_CTX_set(mont: *mut BN_MONT_CTX, m: *const BIGNUM, ctx: *mut BN_CTX) -> c_int;
+    fn BN_mod_mul_montgomery(
+        r: *mut BIGNUM,
+        a: *const BIGNUM,
+        b: *const BIGNUM,
+        mont: *mut BN_MONT_CTX,
+        ctx: *mut BN_CTX,
+    ) -> c_int;
+    fn BN_to_montgomery(
+        r: *mut BIGNUM,
+        a: *const BIGNUM,
+        mont: *mut BN_MONT_CTX,
+        ctx: *mut BN_CTX,
+    ) -> c_int;
+    fn BN_from_montgomery(
+        r: *mut BIGNUM,
+        a: *const BIGNUM,
+        mont: *mut BN_MONT_CTX,
+        ctx: *mut BN_CTX,
+    ) -> c_int;
+}
+
+fn cvt(r: c_int) -> Result<()> {
+    if r <= 0 {
+        return Err(ErrorStack::get().into());
+    }
+    Ok(())
+}
+
+/// Precomputed Montgomery constants for an odd modulus.
+pub struct MontCtx(*mut BN_MONT_CTX);
+
+impl MontCtx {
+    pub fn new(n: &BigNumRef, ctx: &mut BigNumContextRef) -> Result<Self> {
+        unsafe {
+            let mont = BN_MONT_CTX_new();
+            if mont.is_null() {
+                return Err(ErrorStack::get().into());
+            }
+            // Owned from here on, so that it is freed on error.
+            let mont = MontCtx(mont);
+            cvt(BN_MONT_CTX_set(mont.0, n.as_ptr(), ctx.as_ptr()))?;
+            Ok(mont)
+        }
+    }
+
+    /// Converts `a`, which must be reduced, into Montgomery form `aR mod n`.
+    pub fn to_mont(&self, a: &BigNumRef, ctx: &mut BigNumContextRef) -> Result<BigNum> {
+        let r = BigNum::new()?;
+        unsafe {
+            cvt(BN_to_montgomery(
+                r.as_ptr(),
+                a.as_ptr(),
+                self.0,
+                ctx.as_ptr(),
+            ))?
+        };
+        Ok(r)
+    }
+
+    /// Montgomery reduction, converting `a` back out of Montgomery form.
+    pub fn redc(&self, a: &BigNumRef, ctx: &mut BigNumContextRef) -> Result<BigNum> {
+        let r = BigNum::new()?;
+        unsafe {
+            cvt(BN_from_montgomery(
+                r.as_ptr(),
+                a.as_ptr(),
+                self.0,
+                ctx.as_ptr(),
+            ))?
+        };
+        Ok(r)
+    }
+
+    /// Squares a value in Montgomery form in place, without allocating.
+    pub fn square(&self, a: &mut BigNumRef, ctx: &mut BigNumContextRef) -> Result<()> {
+        unsafe {
+            cvt(BN_mod_mul_montgomery(
+                a.as_ptr(),
+                a.as_ptr(),
+                a.as_ptr(),
+                self.0,
+                ctx.as_ptr(),
+            ))
+        }
+    }
+}
+
+impl Drop for MontCtx {
+    fn drop(&mut self) {
+        unsafe { BN_MONT_CTX_free(self.0) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use openssl::bn::BigNumContext;
+
+    use super::*;
+    use crate::rsa::square;
+
+    #[test]
+    fn squares_like_mod_sqr() {
+        let mut ctx = BigNumContext::new().unwrap();
+        let mut n = BigNum::new().unwrap();
+        n.generate_prime(512, false, None, None).unwrap();
+        let mont = MontCtx::new(&n, &mut ctx).unwrap();
+        let mut x = BigNum::new().unwrap();
+        n.rand_range(&mut x).unwrap();
+
+        let mut g = mont.to_mont(&x, &mut ctx).unwrap();
+        assert_eq!(mont.redc(&g, &mut ctx).unwrap(), x);
+        let mut y = x.to_owned().unwrap();
+        for _ in 0..100 {
+            mont.square(&mut g, &mut ctx).unwrap();
+            let mut y2 = BigNum::new().unwrap();
+            y2.mod_sqr(&y, &n, &mut ctx).unwrap();
+            y = y2;
+            assert_eq!(mont.redc(&g, &mut ctx).unwrap(), y);
+        }
+        assert_eq!(square(&x, 100, &n, &mut ctx).unwrap(), y);
+    }
+
+    #[test]
+    fn rejects_even_moduli() {
+        let mut ctx = BigNumContext::new().unwrap();
+        assert!(MontCtx::new(&BigNum::from_u32(1 << 20).unwrap(), &mut ctx).is_err());
+    }
+}
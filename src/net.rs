@@ -0,0 +1,312 @@
+//! A prover daemon and a verifier client running the [`audit`](crate::audit) protocol over TCP.
+//!
+//! Every frame is a big-endian `u32` length followed by the binary [`Encoding`] of a [`Hello`] or an audit
+//! [`Message`]. A connection carries a single audit: the verifier opens with a [`Hello`] naming the file and the
+//! checkpoint spacing and sends the challenge, then the prover streams its checkpoints and the final response.
+
+use std::{
+    collections::HashMap,
+    io::{Read, Write},
+    net::{Shutdown, SocketAddr, TcpListener, TcpStream},
+    path::PathBuf,
+    sync::{
+        mpsc::{self, Receiver, RecvTimeoutError},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant, SystemTime},
+};
+
+use crate::{
+    audit::{AuditProver, AuditVerifier, Finding, Message, Schedule},
+    encoding::Encoding,
+    error::{PostError, Result},
+    modulus::Trapdoor,
+    params::PublicParams,
+    post::{Prove, Store},
+    round::FileMac,
+    rsa::RsaSquaring,
+    source::{Reader, Source},
+    transcript::Transcript,
+    verify::Verdict,
+};
+
+// Far above any checkpoint or response, so that a bogus length cannot make us allocate gigabytes.
+const MAX_FRAME_BYTES: u32 = 1 << 24;
+
+pub fn write_frame<T: Encoding>(w: &mut impl Write, value: &T) -> Result<()> {
+    let body = value.to_binary()?;
+    let len = u32::try_from(body.len())
+        .ok()
+        .filter(|&len| len <= MAX_FRAME_BYTES)
+        .ok_or_else(|| PostError::Encoding(format!("frame of {} bytes", body.len())))?;
+    w.write_all(&len.to_be_bytes())?;
+    w.write_all(&body)?;
+    w.flush()?;
+    Ok(())
+}
+
+pub fn read_frame<T: Encoding>(r: &mut impl Read) -> Result<T> {
+    let mut len = [0; 4];
+    r.read_exact(&mut len)?;
+    let len = u32::from_be_bytes(len);
+    if len > MAX_FRAME_BYTES {
+        return Err(PostError::Encoding(format!("frame of {} bytes", len)));
+    }
+    let mut body = vec![0; len as usize];
+    r.read_exact(&mut body)?;
+    T::from_binary(&body)
+}
+
+/// The first frame of a connection, sent by the verifier.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Hello {
+    /// The name under which the prover hosts the file.
+    pub file: String,
+    /// The checkpoint spacing of the verifier's [`Schedule`].
+    pub every: usize,
+}
+
+struct Hosted {
+    path: PathBuf,
+    public: PublicParams,
+}
+
+/// Holds files and answers audits on them.
+#[derive(Default)]
+pub struct ProverDaemon {
+    files: HashMap<String, Hosted>,
+}
+
+impl ProverDaemon {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Answers audits on the file at `path` under the name `file`, with the chain parameters it was stored with.
+    pub fn host(
+        &mut self,
+        file: impl Into<String>,
+        path: impl Into<PathBuf>,
+        public: PublicParams,
+    ) {
+        self.files.insert(
+            file.into(),
+            Hosted {
+                path: path.into(),
+                public,
+            },
+        );
+    }
+
+    /// Accepts connections until the listener fails, answering each on its own thread.
+    pub fn serve(self, listener: TcpListener) -> Result<()> {
+        let daemon = Arc::new(self);
+        for stream in listener.incoming() {
+            let stream = stream?;
+            let daemon = Arc::clone(&daemon);
+            thread::spawn(move || {
+                // A failed audit only ends its own connection; the verifier sees it as a missing response.
+                let _ = daemon.answer(stream);
+            });
+        }
+        Ok(())
+    }
+
+    /// Answers a single audit on `stream`.
+    pub fn answer(&self, mut stream: TcpStream) -> Result<()> {
+        stream.set_nodelay(true)?;
+        let hello: Hello = read_frame(&mut stream)?;
+        let hosted = self
+            .files
+            .get(&hello.file)
+            .ok_or_else(|| PostError::Protocol(format!("unknown file {}", hello.file)))?;
+        // The prover only needs to know which rounds are checkpoints, not when they are due.
+        let mut prover =
+            AuditProver::new(Schedule::new(hello.every, Duration::ZERO, Duration::ZERO)?);
+        let c = prover.handle(read_frame(&mut stream)?)?;
+        let file = Reader::open(&hosted.path)?;
+        let params = &hosted.public.params;
+        let response = Prove::new(
+            RsaSquaring::new(&hosted.public.modulus, params.squarings)?,
+            params.k,
+        )
+        .with_suite(params.suite)
+        .run_with_rounds(&c, &FileMac(&file), |i, c, v| {
+            if let Some(checkpoint) = prover.on_round(i, c, v)? {
+                write_frame(&mut stream, &checkpoint)?;
+            }
+            Ok(())
+        })?;
+        write_frame(&mut stream, &prover.finish(response)?)
+    }
+}
+
+/// An audit prepared by the verifier at store time, while it still has the file.
+pub struct Audit {
+    pub file: String,
+    /// The verifier's transcript of the chain for the audit's challenge.
+    pub expected: Transcript,
+}
+
+impl Audit {
+    /// Runs the chain over `data` with the trapdoor on a fresh random challenge.
+    pub fn prepare<S: Source + ?Sized>(
+        file: impl Into<String>,
+        data: &S,
+        public: &PublicParams,
+        trapdoor: &Trapdoor,
+    ) -> Result<Self> {
+        if trapdoor.modulus()? != public.modulus {
+            return Err(PostError::InvalidParameters(
+                "the trapdoor does not factor the modulus".into(),
+            ));
+        }
+        let mut c = vec![0; 32];
+        getrandom::getrandom(&mut c)?;
+        let params = &public.params;
+        let (_, expected) = Store::new(
+            RsaSquaring::with_trapdoor(trapdoor, params.squarings)?,
+            params.k,
+        )
+        .with_suite(params.suite)
+        .run_with_transcript(&c, &FileMac(data), false)?;
+        Ok(Self {
+            file: file.into(),
+            expected,
+        })
+    }
+}
+
+/// The outcome of one audit.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AuditRecord {
+    pub file: String,
+    pub started: SystemTime,
+    /// The comparison of the final response with the tag, or `None` if none arrived in time.
+    pub verdict: Option<Verdict>,
+    pub findings: Vec<Finding>,
+}
+
+impl AuditRecord {
+    pub fn passed(&self) -> bool {
+        self.findings.is_empty() && self.verdict.is_some_and(|v| v.is_accepted())
+    }
+}
+
+/// Runs audits against a [`ProverDaemon`] and keeps their records.
+pub struct VerifierClient {
+    addr: SocketAddr,
+    schedule: Schedule,
+    grace: Duration,
+    records: Vec<AuditRecord>,
+}
+
+impl VerifierClient {
+    /// Stops waiting for the final response `grace` after its deadline.
+    pub fn new(addr: SocketAddr, schedule: Schedule, grace: Duration) -> Self {
+        Self {
+            addr,
+            schedule,
+            grace,
+            records: vec![],
+        }
+    }
+
+    /// Runs `audit` to the end and records its outcome.
+    pub fn audit(&mut self, audit: Audit) -> Result<&AuditRecord> {
+        let k = audit.expected.rounds.len().saturating_sub(1);
+        let mut verifier = AuditVerifier::new(audit.expected, self.schedule)?;
+        let mut stream = TcpStream::connect(self.addr)?;
+        stream.set_nodelay(true)?;
+        let hello = Hello {
+            file: audit.file.clone(),
+            every: self.schedule.every(),
+        };
+        let started = SystemTime::now();
+        let start = Instant::now();
+        let challenge = verifier.start(start)?;
+        let give_up = start + self.schedule.response_deadline(k) + self.grace;
+        // The prover may hang up before the challenge is through, e.g. on an unknown file.
+        let opened = write_frame(&mut stream, &hello)
+            .and_then(|()| write_frame(&mut stream, &challenge))
+            .and_then(|()| Ok(receive(stream.try_clone()?)));
+        let violation = match opened {
+            Ok(messages) => wait(&mut verifier, &messages, give_up).err(),
+            Err(_) => None,
+        };
+        if verifier.verdict().is_none() {
+            verifier.abandon();
+        }
+        let _ = stream.shutdown(Shutdown::Both);
+        let mut findings = verifier.findings().to_vec();
+        findings.extend(violation.map(|e| Finding::Violation {
+            reason: e.to_string(),
+        }));
+        self.records.push(AuditRecord {
+            file: audit.file,
+            started,
+            verdict: verifier.verdict(),
+            findings,
+        });
+        Ok(self.records.last().unwrap())
+    }
+
+    /// Runs `audits` one after the other, starting one every `interval` or as soon as the previous one is done.
+    pub fn run(
+        &mut self,
+        audits: impl IntoIterator<Item = Audit>,
+        interval: Duration,
+    ) -> Result<()> {
+        for audit in audits {
+            let next = Instant::now() + interval;
+            self.audit(audit)?;
+            thread::sleep(next.saturating_duration_since(Instant::now()));
+        }
+        Ok(())
+    }
+
+    pub fn records(&self) -> &[AuditRecord] {
+        &self.records
+    }
+}
+
+// Feeds `messages` to the verifier until the response arrives, the prover hangs up, or `give_up`. Fails if the prover
+// sends something undecodable or out of protocol.
+fn wait(
+    verifier: &mut AuditVerifier,
+    messages: &Receiver<Result<Message>>,
+    give_up: Instant,
+) -> Result<()> {
+    while verifier.verdict().is_none() {
+        let now = Instant::now();
+        if now >= give_up {
+            break;
+        }
+        let wake = verifier.next_deadline().map_or(give_up, |d| d.min(give_up));
+        match messages.recv_timeout(wake.saturating_duration_since(now)) {
+            Ok(Ok(message)) => {
+                verifier.handle(message, Instant::now())?;
+            }
+            Err(RecvTimeoutError::Timeout) => {
+                verifier.poll(Instant::now());
+            }
+            Ok(Err(e @ PostError::Encoding(_))) => return Err(e),
+            Ok(Err(_)) | Err(RecvTimeoutError::Disconnected) => break,
+        }
+    }
+    Ok(())
+}
+
+// Reads messages on a thread of its own, so that the verifier can wait for them with a timeout.
+fn receive(mut stream: TcpStream) -> Receiver<Result<Message>> {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || loop {
+        let message = read_frame(&mut stream);
+        let failed = message.is_err();
+        if tx.send(message).is_err() || failed {
+            return;
+        }
+    });
+    rx
+}
@@ -0,0 +1,133 @@
+use crate::{
+    error::{PostError, Result},
+    hash::Suite,
+    modulus::Modulus,
+};
+
+pub const MIN_MODULUS_BITS: u32 = 1024;
+pub const MAX_MODULUS_BITS: u32 = 16384;
+pub const MAX_ROUNDS: usize = 1 << 24;
+// Beyond 2^40 squarings a single round would run for days.
+pub const MAX_SQUARINGS: u64 = 1 << 40;
+
+/// The public parameters of a PoST instance: the RSA modulus size, the number `k` of delays in the chain, the
+/// number of squarings in each delay and the hash suite of the chain.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Params {
+    pub n_bits: u32,
+    pub k: usize,
+    pub squarings: u64,
+    pub suite: Suite,
+}
+
+impl Params {
+    /// Uses the default [`Suite`].
+    pub fn new(n_bits: u32, k: usize, squarings: u64) -> Result<Self> {
+        let params = Self {
+            n_bits,
+            k,
+            squarings,
+            suite: Suite::default(),
+        };
+        params.validate()?;
+        Ok(params)
+    }
+
+    pub fn with_suite(self, suite: Suite) -> Self {
+        Self { suite, ..self }
+    }
+
+    /// Checks every field against its bounds, e.g. after parsing.
+    pub fn validate(&self) -> Result<()> {
+        check_modulus_bits(self.n_bits)?;
+        check_rounds(self.k)?;
+        check_squarings(self.squarings)
+    }
+}
+
+/// What the prover and any third-party verifier need: the modulus and the [`Params`] it was generated for.
+#[derive(Debug, PartialEq, Eq)]
+pub struct PublicParams {
+    pub modulus: Modulus,
+    pub params: Params,
+}
+
+impl PublicParams {
+    pub fn new(modulus: Modulus, params: Params) -> Result<Self> {
+        params.validate()?;
+        if modulus.bits() != params.n_bits {
+            return Err(PostError::InvalidParameters(format!(
+                "modulus of {} bits does not match n_bits = {}",
+                modulus.bits(),
+                params.n_bits
+            )));
+        }
+        Ok(Self { modulus, params })
+    }
+}
+
+pub fn check_modulus_bits(n_bits: u32) -> Result<()> {
+    if !(MIN_MODULUS_BITS..=MAX_MODULUS_BITS).contains(&n_bits) {
+        return Err(PostError::InvalidParameters(format!(
+            "modulus of {} bits is outside [{}, {}]",
+            n_bits, MIN_MODULUS_BITS, MAX_MODULUS_BITS
+        )));
+    }
+    Ok(())
+}
+
+pub fn check_rounds(k: usize) -> Result<()> {
+    if !(1..=MAX_ROUNDS).contains(&k) {
+        return Err(PostError::InvalidParameters(format!(
+            "{} delays is outside [1, {}]",
+            k, MAX_ROUNDS
+        )));
+    }
+    Ok(())
+}
+
+pub fn check_squarings(squarings: u64) -> Result<()> {
+    if !(1..=MAX_SQUARINGS).contains(&squarings) {
+        return Err(PostError::InvalidParameters(format!(
+            "{} squarings is outside [1, {}]",
+            squarings, MAX_SQUARINGS
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bounds() {
+        assert!(Params::new(MIN_MODULUS_BITS, 1, 1).is_ok());
+        assert!(Params::new(MAX_MODULUS_BITS, MAX_ROUNDS, MAX_SQUARINGS).is_ok());
+        for (n_bits, k, squarings) in [
+            (MIN_MODULUS_BITS - 1, 1, 1),
+            (512, 1, 1),
+            (0, 1, 1),
+            (MAX_MODULUS_BITS + 1, 1, 1),
+            (MIN_MODULUS_BITS, 0, 1),
+            (MIN_MODULUS_BITS, MAX_ROUNDS + 1, 1),
+            (MIN_MODULUS_BITS, 1, 0),
+            (MIN_MODULUS_BITS, 1, MAX_SQUARINGS + 1),
+        ] {
+            assert!(matches!(
+                Params::new(n_bits, k, squarings),
+                Err(PostError::InvalidParameters(_))
+            ));
+        }
+
+        // Fields set directly, e.g. by a decoder, are caught by `validate`.
+        let params = Params {
+            k: 0,
+            ..Params::new(MIN_MODULUS_BITS, 1, 1).unwrap()
+        };
+        assert!(matches!(
+            params.validate(),
+            Err(PostError::InvalidParameters(_))
+        ));
+    }
+}
@@ -0,0 +1,337 @@
+//! Private-key compact proof of retrievability by Shacham and Waters.
+//!
+//! The file is split into blocks of `s` sectors, each sector being an element of `Z_p`. The data owner holds a PRF key
+//! and `α_1, ..., α_s`, and tags block `i` with `σ_i = f(i) + Σ_j α_j m_ij`. A challenge picks `l` blocks with
+//! coefficients `ν_i`, and the response `μ_j = Σ ν_i m_ij`, `σ = Σ ν_i σ_i` costs `O(l * s)` instead of a pass over the
+//! whole file.
+
+use openssl::{
+    bn::{BigNum, BigNumContext, BigNumRef},
+    rand::rand_bytes,
+};
+
+use crate::{
+    error::{PostError, Result},
+    hash::{Domain, Suite, FIXED_SUITE},
+    round::{sample, Round},
+    source::Source,
+};
+
+// 2^255 - 19
+const P_HEX: &str = "7FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFED";
+// Sectors are strictly smaller than `p`.
+const SECTOR_BYTES: usize = 31;
+const ELEMENT_BYTES: i32 = 32;
+
+fn prime() -> Result<BigNum> {
+    Ok(BigNum::from_hex_str(P_HEX)?)
+}
+
+fn reduce(bytes: &[u8], p: &BigNumRef, ctx: &mut BigNumContext) -> Result<BigNum> {
+    let x = BigNum::from_slice(bytes)?;
+    let mut r = BigNum::new()?;
+    r.nnmod(&x, p, ctx)?;
+    Ok(r)
+}
+
+// acc += a * b (mod p)
+fn mul_add(
+    acc: &mut BigNum,
+    a: &BigNumRef,
+    b: &BigNumRef,
+    p: &BigNumRef,
+    ctx: &mut BigNumContext,
+) -> Result<()> {
+    let mut t = BigNum::new()?;
+    t.mod_mul(a, b, p, ctx)?;
+    let mut r = BigNum::new()?;
+    r.mod_add(acc, &t, p, ctx)?;
+    *acc = r;
+    Ok(())
+}
+
+fn block_count(len: u64, sectors: usize) -> usize {
+    len.div_ceil((SECTOR_BYTES * sectors) as u64) as usize
+}
+
+// The sectors of block `i`, zero past the end of the file.
+fn block<S: Source + ?Sized>(d: &S, sectors: usize, i: usize) -> Result<Vec<BigNum>> {
+    let mut buf = vec![0; sectors * SECTOR_BYTES];
+    let n = d.read_at((i * sectors * SECTOR_BYTES) as u64, &mut buf)?;
+    buf.truncate(n);
+    (0..sectors)
+        .map(|j| {
+            let start = usize::min(j * SECTOR_BYTES, n);
+            let end = usize::min(start + SECTOR_BYTES, n);
+            Ok(BigNum::from_slice(&buf[start..end])?)
+        })
+        .collect()
+}
+
+/// Expands a round challenge into `l` block indices and coefficients with the chain's `suite`.
+pub fn challenge(suite: Suite, c: &[u8], blocks: usize, l: usize) -> Result<Vec<(usize, BigNum)>> {
+    let p = prime()?;
+    let mut ctx = BigNumContext::new()?;
+    sample(suite, c, b"por-index", blocks, l)?
+        .into_iter()
+        .zip(0u64..)
+        .map(|(i, idx)| {
+            let h = suite.mac(
+                Domain::Challenge,
+                c,
+                &[b"por-coeff".as_ref(), &idx.to_be_bytes()].concat(),
+            )?;
+            Ok((i, reduce(&h, &p, &mut ctx)?))
+        })
+        .collect()
+}
+
+/// The data owner's secret key.
+pub struct PorKey {
+    prf: [u8; 32],
+    alphas: Vec<BigNum>,
+}
+
+impl PorKey {
+    pub fn generate(sectors: usize) -> Result<Self> {
+        if sectors == 0 {
+            return Err(PostError::InvalidParameters(
+                "a block needs at least one sector".into(),
+            ));
+        }
+        let p = prime()?;
+        let mut prf = [0; 32];
+        rand_bytes(&mut prf)?;
+        let alphas = (0..sectors)
+            .map(|_| {
+                let mut a = BigNum::new()?;
+                p.rand_range(&mut a)?;
+                Ok(a)
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self { prf, alphas })
+    }
+
+    pub fn sectors(&self) -> usize {
+        self.alphas.len()
+    }
+
+    fn f(&self, i: usize, p: &BigNumRef, ctx: &mut BigNumContext) -> Result<BigNum> {
+        let h = FIXED_SUITE.mac(Domain::Authenticator, &self.prf, &(i as u64).to_be_bytes())?;
+        reduce(&h, p, ctx)
+    }
+
+    /// Computes the authenticator of every block; these are handed to the prover together with the file.
+    pub fn tags<S: Source + ?Sized>(&self, d: &S) -> Result<Vec<BigNum>> {
+        if d.size()? == 0 {
+            return Err(PostError::InvalidParameters(
+                "cannot tag an empty file".into(),
+            ));
+        }
+        let p = prime()?;
+        let mut ctx = BigNumContext::new()?;
+        (0..block_count(d.size()?, self.sectors()))
+            .map(|i| {
+                let mut sigma = self.f(i, &p, &mut ctx)?;
+                for (alpha, m) in self.alphas.iter().zip(block(d, self.sectors(), i)?) {
+                    mul_add(&mut sigma, alpha, &m, &p, &mut ctx)?;
+                }
+                Ok(sigma)
+            })
+            .collect()
+    }
+
+    /// Checks a single round response of `blocks` blocks against the challenge `c` of a chain hashed with `suite`.
+    pub fn check(
+        &self,
+        suite: Suite,
+        c: &[u8],
+        blocks: usize,
+        l: usize,
+        response: &[u8],
+    ) -> Result<bool> {
+        let s = self.sectors();
+        if response.len() != (s + 1) * ELEMENT_BYTES as usize {
+            return Err(PostError::MalformedProof(
+                "unexpected PoR response length".into(),
+            ));
+        }
+        let p = prime()?;
+        let mut ctx = BigNumContext::new()?;
+        let mut elements = response
+            .chunks(ELEMENT_BYTES as usize)
+            .map(BigNum::from_slice)
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        let sigma = elements.pop().unwrap();
+        let mut expected = BigNum::new()?;
+        for (i, nu) in challenge(suite, c, blocks, l)? {
+            let f = self.f(i, &p, &mut ctx)?;
+            mul_add(&mut expected, &nu, &f, &p, &mut ctx)?;
+        }
+        for (alpha, mu) in self.alphas.iter().zip(&elements) {
+            mul_add(&mut expected, alpha, mu, &p, &mut ctx)?;
+        }
+        Ok(expected == sigma)
+    }
+}
+
+/// Round function answering sampled block challenges from the file and its tags.
+pub struct PorRound<'a, S: ?Sized = [u8]> {
+    d: &'a S,
+    tags: &'a [BigNum],
+    sectors: usize,
+    l: usize,
+}
+
+impl<'a, S: Source + ?Sized> PorRound<'a, S> {
+    /// `l` is the number of blocks challenged per round.
+    pub fn new(d: &'a S, tags: &'a [BigNum], sectors: usize, l: usize) -> Result<Self> {
+        if sectors == 0 || l == 0 {
+            return Err(PostError::InvalidParameters(
+                "sectors and challenged blocks must be positive".into(),
+            ));
+        }
+        if d.size()? == 0 {
+            return Err(PostError::InvalidParameters(
+                "cannot prove an empty file".into(),
+            ));
+        }
+        if tags.len() != block_count(d.size()?, sectors) {
+            return Err(PostError::InvalidParameters(
+                "tag count does not match the file".into(),
+            ));
+        }
+        Ok(Self {
+            d,
+            tags,
+            sectors,
+            l,
+        })
+    }
+
+    pub fn blocks(&self) -> usize {
+        self.tags.len()
+    }
+}
+
+impl<S: Source + ?Sized> Round for PorRound<'_, S> {
+    fn respond(&self, suite: Suite, c: &[u8]) -> Result<Vec<u8>> {
+        let p = prime()?;
+        let mut ctx = BigNumContext::new()?;
+        let mut mus = (0..self.sectors)
+            .map(|_| BigNum::new())
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        let mut sigma = BigNum::new()?;
+        for (i, nu) in challenge(suite, c, self.blocks(), self.l)? {
+            for (mu, m) in mus.iter_mut().zip(block(self.d, self.sectors, i)?) {
+                mul_add(mu, &nu, &m, &p, &mut ctx)?;
+            }
+            mul_add(&mut sigma, &nu, &self.tags[i], &p, &mut ctx)?;
+        }
+        let mut v = vec![];
+        for x in mus.iter().chain([&sigma]) {
+            v.extend_from_slice(&x.to_vec_padded(ELEMENT_BYTES)?);
+        }
+        Ok(v)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECTORS: usize = 3;
+    const L: usize = 4;
+
+    fn file() -> Vec<u8> {
+        (0..1000).map(|i| (i * 7) as u8).collect()
+    }
+
+    #[test]
+    fn roundtrip() {
+        let d = file();
+        let key = PorKey::generate(SECTORS).unwrap();
+        let tags = key.tags(&d[..]).unwrap();
+        assert_eq!(tags.len(), block_count(d.len() as u64, SECTORS));
+        let round = PorRound::new(&d[..], &tags, SECTORS, L).unwrap();
+        for c in [&b"c"[..], b"another challenge"] {
+            let v = round.respond(Suite::default(), c).unwrap();
+            assert!(key
+                .check(Suite::default(), c, round.blocks(), L, &v)
+                .unwrap());
+            assert!(!key
+                .check(Suite::default(), b"other", round.blocks(), L, &v)
+                .unwrap());
+        }
+    }
+
+    #[test]
+    fn suites() {
+        let d = file();
+        let key = PorKey::generate(SECTORS).unwrap();
+        let tags = key.tags(&d[..]).unwrap();
+        let round = PorRound::new(&d[..], &tags, SECTORS, L).unwrap();
+        for suite in Suite::ALL {
+            let v = round.respond(suite, b"c").unwrap();
+            assert!(key.check(suite, b"c", tags.len(), L, &v).unwrap());
+        }
+        let v = round.respond(Suite::Kmac256, b"c").unwrap();
+        assert!(!key.check(Suite::Blake3, b"c", tags.len(), L, &v).unwrap());
+    }
+
+    #[test]
+    fn rejects_tampering() {
+        let d = file();
+        let key = PorKey::generate(SECTORS).unwrap();
+        let tags = key.tags(&d[..]).unwrap();
+        let c = b"c";
+        let v = PorRound::new(&d[..], &tags, SECTORS, L)
+            .unwrap()
+            .respond(Suite::default(), c)
+            .unwrap();
+        for i in [0, v.len() - 1] {
+            let mut forged = v.clone();
+            forged[i] ^= 1;
+            assert!(!key
+                .check(Suite::default(), c, tags.len(), L, &forged)
+                .unwrap());
+        }
+        assert!(key
+            .check(Suite::default(), c, tags.len(), L, &v[1..])
+            .is_err());
+
+        // Enough samples to hit the block with the changed byte.
+        let mut corrupted = d.clone();
+        corrupted[500] ^= 1;
+        let v = PorRound::new(&corrupted[..], &tags, SECTORS, 64)
+            .unwrap()
+            .respond(Suite::default(), c)
+            .unwrap();
+        assert!(!key.check(Suite::default(), c, tags.len(), 64, &v).unwrap());
+
+        let other = PorKey::generate(SECTORS).unwrap().tags(&d[..]).unwrap();
+        let v = PorRound::new(&d[..], &other, SECTORS, L)
+            .unwrap()
+            .respond(Suite::default(), c)
+            .unwrap();
+        assert!(!key.check(Suite::default(), c, tags.len(), L, &v).unwrap());
+        assert!(PorRound::new(&d[..], &tags[1..], SECTORS, L).is_err());
+    }
+
+    #[test]
+    fn rejects_empty_files() {
+        let key = PorKey::generate(2).unwrap();
+        assert!(matches!(
+            key.tags(b""),
+            Err(PostError::InvalidParameters(_))
+        ));
+        assert!(matches!(
+            PorRound::new(b"", &[], 2, 1),
+            Err(PostError::InvalidParameters(_))
+        ));
+        assert!(matches!(
+            key.check(Suite::default(), b"c", 0, 1, &[0; 96]),
+            Err(PostError::InvalidParameters(_))
+        ));
+    }
+}
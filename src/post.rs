@@ -0,0 +1,197 @@
+use crate::{
+    delay::DelayFunction,
+    error::Result,
+    hash::{Domain, Suite},
+    round::Round,
+    transcript::{Transcript, TranscriptRound},
+};
+
+/// Digests of the challenges and round responses of a whole chain, and the suite they were computed with.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Tag {
+    pub cs: Vec<u8>,
+    pub vs: Vec<u8>,
+    pub suite: Suite,
+}
+
+/// What the prover hands back to the verifier; same shape as the [`Tag`].
+pub type Response = Tag;
+
+// Runs `k + 1` rounds, deriving each challenge from the delay function applied to the previous round response. The
+// delay is called with its input, the round challenge and the round response.
+fn chain<R: Round + ?Sized>(
+    c: &[u8],
+    k: usize,
+    suite: Suite,
+    round: &R,
+    mut delay: impl FnMut(&[u8], &[u8], &[u8]) -> Result<Vec<u8>>,
+) -> Result<Tag> {
+    let mut c = c.to_vec();
+    let mut cs = vec![];
+    let mut vs = vec![];
+    for _ in 0..=k {
+        let v = round.respond(suite, &c)?;
+        cs.extend_from_slice(&c);
+        vs.extend_from_slice(&v);
+        c = suite.hash(
+            Domain::Challenge,
+            &delay(&suite.hash(Domain::DelayInput, &v)?, &c, &v)?,
+        )?;
+    }
+    Ok(Tag {
+        cs: suite.hash(Domain::Transcript, &cs)?,
+        vs: suite.hash(Domain::Transcript, &vs)?,
+        suite,
+    })
+}
+
+fn transcript<R: Round + ?Sized>(
+    c: &[u8],
+    k: usize,
+    suite: Suite,
+    round: &R,
+    outputs: bool,
+    mut delay: impl FnMut(&[u8]) -> Result<Vec<u8>>,
+) -> Result<(Tag, Transcript)> {
+    let mut rounds = vec![];
+    let tag = chain(c, k, suite, round, |x, c, v| {
+        let y = delay(x)?;
+        rounds.push(TranscriptRound {
+            c: c.to_vec(),
+            v: v.to_vec(),
+            y: outputs.then(|| y.clone()),
+        });
+        Ok(y)
+    })?;
+    Ok((tag, Transcript { suite, rounds }))
+}
+
+/// One round of a publicly verifiable chain: the round response `v`, the delay output `y` on the digest of `v` and its
+/// proof.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RoundProof {
+    pub v: Vec<u8>,
+    pub y: Vec<u8>,
+    pub proof: Vec<u8>,
+}
+
+/// The per-round delay proofs of a whole chain.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ChainProof {
+    pub rounds: Vec<RoundProof>,
+}
+
+/// Verifier side: computes the tag via the trapdoor of the delay function.
+pub struct Store<D> {
+    delay: D,
+    k: usize,
+    suite: Suite,
+}
+
+impl<D: DelayFunction> Store<D> {
+    pub fn new(delay: D, k: usize) -> Self {
+        Self {
+            delay,
+            k,
+            suite: Suite::default(),
+        }
+    }
+
+    /// Hashes with `suite` instead of the default, e.g. the one of the [`Params`](crate::Params).
+    pub fn with_suite(self, suite: Suite) -> Self {
+        Self { suite, ..self }
+    }
+
+    pub fn run<R: Round + ?Sized>(&self, c: &[u8], round: &R) -> Result<Tag> {
+        chain(c, self.k, self.suite, round, |x, _, _| {
+            self.delay.eval_trapdoor(x)
+        })
+    }
+
+    /// Like [`Store::run`], but also records every round, with its delay output if `outputs` is set.
+    pub fn run_with_transcript<R: Round + ?Sized>(
+        &self,
+        c: &[u8],
+        round: &R,
+        outputs: bool,
+    ) -> Result<(Tag, Transcript)> {
+        transcript(c, self.k, self.suite, round, outputs, |x| {
+            self.delay.eval_trapdoor(x)
+        })
+    }
+}
+
+/// Prover side: walks the chain with the public evaluation only.
+pub struct Prove<D> {
+    delay: D,
+    k: usize,
+    suite: Suite,
+}
+
+impl<D: DelayFunction> Prove<D> {
+    pub fn new(delay: D, k: usize) -> Self {
+        Self {
+            delay,
+            k,
+            suite: Suite::default(),
+        }
+    }
+
+    /// Hashes with `suite` instead of the default; it must be the one the verifier stored with.
+    pub fn with_suite(self, suite: Suite) -> Self {
+        Self { suite, ..self }
+    }
+
+    pub fn run<R: Round + ?Sized>(&self, c: &[u8], round: &R) -> Result<Response> {
+        chain(c, self.k, self.suite, round, |x, _, _| self.delay.eval(x))
+    }
+
+    /// Like [`Prove::run`], but hands every round `(i, c_i, v_i)` to `on_round` as soon as it is answered, before its
+    /// delay runs, e.g. to send the checkpoints of an [`audit`](crate::audit).
+    pub fn run_with_rounds<R: Round + ?Sized>(
+        &self,
+        c: &[u8],
+        round: &R,
+        mut on_round: impl FnMut(usize, &[u8], &[u8]) -> Result<()>,
+    ) -> Result<Response> {
+        let mut i = 0;
+        chain(c, self.k, self.suite, round, |x, c, v| {
+            on_round(i, c, v)?;
+            i += 1;
+            self.delay.eval(x)
+        })
+    }
+
+    /// Like [`Prove::run`], but also records every round, with its delay output if `outputs` is set, so that an
+    /// auditor can locate the first round that disagrees with the verifier's [`Transcript`].
+    pub fn run_with_transcript<R: Round + ?Sized>(
+        &self,
+        c: &[u8],
+        round: &R,
+        outputs: bool,
+    ) -> Result<(Response, Transcript)> {
+        transcript(c, self.k, self.suite, round, outputs, |x| {
+            self.delay.eval(x)
+        })
+    }
+
+    /// Like [`Prove::run`], but also emits a proof for every delay evaluation so that anyone can check the chain with
+    /// [`verify_chain`](crate::verify_chain), without a trapdoor.
+    pub fn run_with_proofs<R: Round + ?Sized>(
+        &self,
+        c: &[u8],
+        round: &R,
+    ) -> Result<(Response, ChainProof)> {
+        let mut rounds = vec![];
+        let response = chain(c, self.k, self.suite, round, |x, _, v| {
+            let (y, proof) = self.delay.prove(x)?;
+            rounds.push(RoundProof {
+                v: v.to_vec(),
+                y: y.clone(),
+                proof,
+            });
+            Ok(y)
+        })?;
+        Ok((response, ChainProof { rounds }))
+    }
+}
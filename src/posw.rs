@@ -0,0 +1,227 @@
+//! Hash-based proof of sequential work by Cohen and Pietrzak.
+//!
+//! The statement `χ` keys a SHA3 labelling of a DAG on the complete binary tree of depth `n`: an inner node depends on
+//! its two children, and every leaf additionally depends on the left siblings of its path to the root, so the
+//! `2^(n+1) - 1` labels can only be computed one after the other. The root label `φ` is both the output and a Merkle
+//! commitment to all labels. The proof opens a few leaves chosen by the random oracle `H(χ, φ)`; the verifier recomputes
+//! each leaf from its left siblings and hashes up to `φ`, spot-checking the DAG instead of recomputing it.
+//!
+//! The prover keeps only the top levels of the DAG and recomputes the subtree below each opened leaf, so its memory
+//! stays bounded for any depth.
+//!
+//! There is no trapdoor, so only the public [`verify`](DelayFunction::verify) is available to the verifier.
+
+use crate::{
+    delay::DelayFunction,
+    error::{PostError, Result},
+    hash::{sha3, Domain, FIXED_SUITE},
+};
+
+const LABEL_BYTES: usize = 32;
+// The levels above the leaves' subtrees that `prove` keeps, 4 MiB of labels. Recomputing the subtrees of the opened
+// leaves adds about `checks / 2^KEPT_LEVELS` to the work of the evaluation.
+const KEPT_LEVELS: usize = 16;
+
+pub struct HashChainPosw {
+    n: usize,
+    checks: usize,
+}
+
+impl HashChainPosw {
+    /// A DAG of depth `n` (`2^(n+1) - 1` sequential hashes) whose proofs open `checks` leaves.
+    pub fn new(n: usize, checks: usize) -> Result<Self> {
+        if n == 0 || n > 40 {
+            return Err(PostError::InvalidParameters(format!(
+                "DAG depth {} is outside 1..=40",
+                n
+            )));
+        }
+        if checks == 0 {
+            return Err(PostError::InvalidParameters(
+                "at least one leaf must be checked".into(),
+            ));
+        }
+        Ok(Self { n, checks })
+    }
+
+    // The label of the node at `height` and `index`, over its parents in order.
+    fn label(&self, chi: &[u8], height: usize, index: u64, parents: &[&[u8]]) -> Result<Vec<u8>> {
+        let mut data = vec![];
+        data.extend_from_slice(b"posw");
+        data.extend_from_slice(chi);
+        data.push(height as u8);
+        data.extend_from_slice(&index.to_be_bytes());
+        for p in parents {
+            data.extend_from_slice(p);
+        }
+        Ok(sha3(&data)?.to_vec())
+    }
+
+    // Labels the subtree of `height` at `index` in depth-first order, passing every node to `store`, and returns its
+    // root label. `above` are the left siblings of the subtree's path to the root, from the top down.
+    fn subtree(
+        &self,
+        chi: &[u8],
+        height: usize,
+        index: u64,
+        above: &[&[u8]],
+        mut store: impl FnMut(usize, u64, &[u8]),
+    ) -> Result<Vec<u8>> {
+        // Completed subtrees to the left of the current leaf within this one; together with `above`, exactly the left
+        // siblings of its path.
+        let mut stack: Vec<(usize, Vec<u8>)> = vec![];
+        let first = index << height;
+        for leaf in first..first + (1 << height) {
+            let parents = above
+                .iter()
+                .copied()
+                .chain(stack.iter().map(|(_, l)| &l[..]))
+                .collect::<Vec<_>>();
+            let mut label = self.label(chi, 0, leaf, &parents)?;
+            store(0, leaf, &label);
+            let (mut height, mut index) = (0, leaf);
+            while matches!(stack.last(), Some((h, _)) if *h == height) {
+                let (_, left) = stack.pop().unwrap();
+                height += 1;
+                index >>= 1;
+                label = self.label(chi, height, index, &[&left, &label])?;
+                store(height, index, &label);
+            }
+            stack.push((height, label));
+        }
+        Ok(stack.pop().unwrap().1)
+    }
+
+    fn challenges(&self, chi: &[u8], phi: &[u8]) -> Result<Vec<u64>> {
+        (0..self.checks as u64)
+            .map(|i| {
+                let h = FIXED_SUITE.hash(
+                    Domain::ProofChallenge,
+                    &[b"posw-challenge".as_ref(), chi, phi, &i.to_be_bytes()].concat(),
+                )?;
+                Ok(u64::from_be_bytes(h[..8].try_into().unwrap()) & ((1 << self.n) - 1))
+            })
+            .collect()
+    }
+}
+
+impl DelayFunction for HashChainPosw {
+    fn eval(&self, x: &[u8]) -> Result<Vec<u8>> {
+        self.subtree(x, self.n, 0, &[], |_, _, _| {})
+    }
+
+    fn prove(&self, x: &[u8]) -> Result<(Vec<u8>, Vec<u8>)> {
+        // top[h - low] is the level at height h.
+        let low = self.n.saturating_sub(KEPT_LEVELS);
+        let mut top = (low..=self.n)
+            .map(|h| vec![[0; LABEL_BYTES]; 1 << (self.n - h)])
+            .collect::<Vec<_>>();
+        let phi = self.subtree(x, self.n, 0, &[], |h, i, label| {
+            if h >= low {
+                top[h - low][i as usize].copy_from_slice(label)
+            }
+        })?;
+        let mut proof = vec![];
+        for leaf in self.challenges(x, &phi)? {
+            let mut siblings = vec![[0; LABEL_BYTES]; self.n];
+            for h in low..self.n {
+                siblings[h] = top[h - low][((leaf >> h) ^ 1) as usize];
+            }
+            let index = leaf >> low;
+            let above = (low..self.n)
+                .rev()
+                .filter(|h| (leaf >> h) & 1 == 1)
+                .map(|h| &siblings[h][..])
+                .collect::<Vec<_>>();
+            let mut below = vec![[0; LABEL_BYTES]; low];
+            self.subtree(x, low, index, &above, |h, i, label| {
+                if h < low && i == (leaf >> h) ^ 1 {
+                    below[h].copy_from_slice(label)
+                }
+            })?;
+            siblings[..low].copy_from_slice(&below);
+            for sibling in &siblings {
+                proof.extend_from_slice(sibling);
+            }
+        }
+        Ok((phi, proof))
+    }
+
+    fn verify(&self, x: &[u8], y: &[u8], proof: &[u8]) -> Result<bool> {
+        let opening = self.n * LABEL_BYTES;
+        if proof.len() != self.checks * opening || y.len() != LABEL_BYTES {
+            return Err(PostError::MalformedProof(
+                "unexpected proof of sequential work length".into(),
+            ));
+        }
+        for (leaf, siblings) in self
+            .challenges(x, y)?
+            .into_iter()
+            .zip(proof.chunks(opening))
+        {
+            let siblings = siblings.chunks(LABEL_BYTES).collect::<Vec<_>>();
+            // The leaf's parents are the siblings on the left of its path, from the top down.
+            let parents = (0..self.n)
+                .rev()
+                .filter(|h| (leaf >> h) & 1 == 1)
+                .map(|h| siblings[h])
+                .collect::<Vec<_>>();
+            let mut label = self.label(x, 0, leaf, &parents)?;
+            for (h, sibling) in siblings.iter().enumerate() {
+                let index = leaf >> (h + 1);
+                label = if (leaf >> h) & 1 == 0 {
+                    self.label(x, h + 1, index, &[&label, sibling])?
+                } else {
+                    self.label(x, h + 1, index, &[sibling, &label])?
+                };
+            }
+            if label != y {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn roundtrip(n: usize) {
+        let posw = HashChainPosw::new(n, 4).unwrap();
+        let (y, proof) = posw.prove(b"x").unwrap();
+        assert_eq!(y, posw.eval(b"x").unwrap());
+        assert!(posw.verify(b"x", &y, &proof).unwrap());
+        assert!(!posw.verify(b"other", &y, &proof).unwrap());
+
+        let mut wrong = y.clone();
+        wrong[0] ^= 1;
+        assert!(!posw.verify(b"x", &wrong, &proof).unwrap());
+        for i in [0, proof.len() / 2, proof.len() - 1] {
+            let mut forged = proof.clone();
+            forged[i] ^= 1;
+            assert!(!posw.verify(b"x", &y, &forged).unwrap());
+        }
+        assert!(posw.verify(b"x", &y, &proof[1..]).is_err());
+    }
+
+    #[test]
+    fn small_dags() {
+        for n in 1..=6 {
+            roundtrip(n);
+        }
+    }
+
+    // Deeper than the levels kept in memory, so that the opened subtrees are recomputed.
+    #[test]
+    fn recomputed_subtrees() {
+        roundtrip(KEPT_LEVELS + 1);
+    }
+
+    #[test]
+    fn rejects_invalid_depths() {
+        assert!(HashChainPosw::new(0, 1).is_err());
+        assert!(HashChainPosw::new(41, 1).is_err());
+        assert!(HashChainPosw::new(1, 0).is_err());
+    }
+}
@@ -0,0 +1,45 @@
+use crate::{
+    error::{PostError, Result},
+    hash::{Domain, Suite},
+    source::Source,
+};
+
+/// The per-round function that binds the chain to the stored data.
+///
+/// Given the round challenge `c`, it returns the response `v` that is fed into the delay function. Rounds that MAC the
+/// data do so with the chain's `suite` under [`Domain::RoundMac`], and rounds that sample it expand `c` into the samples
+/// under [`Domain::Challenge`].
+pub trait Round {
+    fn respond(&self, suite: Suite, c: &[u8]) -> Result<Vec<u8>>;
+}
+
+/// The original round: a MAC over the whole file keyed by the challenge.
+pub struct FileMac<'a, S: ?Sized = [u8]>(pub &'a S);
+
+impl<S: Source + ?Sized> Round for FileMac<'_, S> {
+    fn respond(&self, suite: Suite, c: &[u8]) -> Result<Vec<u8>> {
+        suite.mac_source(Domain::RoundMac, c, self.0)
+    }
+}
+
+// Expands the challenge `c` into `l` indices below `n`: index `idx` is taken from the MAC under `c` of `label` and `idx`
+// in `Domain::Challenge`, where `label` separates the rounds that sample this way.
+pub(crate) fn sample(
+    suite: Suite,
+    c: &[u8],
+    label: &[u8],
+    n: usize,
+    l: usize,
+) -> Result<Vec<usize>> {
+    if n == 0 {
+        return Err(PostError::InvalidParameters(
+            "cannot challenge an empty file".into(),
+        ));
+    }
+    (0..l as u64)
+        .map(|idx| {
+            let h = suite.mac(Domain::Challenge, c, &[label, &idx.to_be_bytes()].concat())?;
+            Ok((u64::from_be_bytes(h[..8].try_into().unwrap()) % n as u64) as usize)
+        })
+        .collect()
+}
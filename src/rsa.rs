@@ -0,0 +1,251 @@
+#[cfg(feature = "openssl")]
+use openssl::bn::{BigNum, BigNumContext, BigNumRef};
+
+use crate::{
+    backend::{BigIntBackend, DefaultBackend},
+    delay::DelayFunction,
+    error::{PostError, Result},
+    modulus::{Modulus, Trapdoor},
+    params::check_squarings,
+};
+#[cfg(feature = "openssl")]
+use crate::{
+    mont::MontCtx,
+    vdf::{self, ProofKind},
+};
+
+/// The factorization in CRT form: the trapdoor exponent `2^T` reduced modulo `p - 1` and `q - 1`.
+pub struct CrtTrapdoor<B: BigIntBackend> {
+    p: B::Int,
+    q: B::Int,
+    ep: B::Int,
+    eq: B::Int,
+    // q^-1 mod p
+    q_inv: B::Int,
+}
+
+impl<B: BigIntBackend> CrtTrapdoor<B> {
+    pub fn new(p: B::Int, q: B::Int, squarings: u64) -> Result<Self> {
+        Ok(Self {
+            ep: reduced_exponent::<B>(&p, squarings)?,
+            eq: reduced_exponent::<B>(&q, squarings)?,
+            q_inv: B::mod_inverse(&q, &p)?,
+            p,
+            q,
+        })
+    }
+}
+
+// 2^T mod (p - 1), from the same squaring count that `eval` runs
+fn reduced_exponent<B: BigIntBackend>(p: &B::Int, squarings: u64) -> Result<B::Int> {
+    let p1 = B::sub(p, &B::from_u64(1)?)?;
+    B::mod_exp(&B::from_u64(2)?, &B::from_u64(squarings)?, &p1)
+}
+
+// Exponentiates modulo `p` and `q` separately and recombines with Garner's formula `y = yq + q * (q^-1 (yp - yq) mod p)`.
+pub fn eval_trap<B: BigIntBackend>(x: &[u8], trapdoor: &CrtTrapdoor<B>) -> Result<Vec<u8>> {
+    let CrtTrapdoor {
+        p,
+        q,
+        ep,
+        eq,
+        q_inv,
+    } = trapdoor;
+    let x = B::from_bytes(x)?;
+    let yp = B::mod_exp(&B::rem(&x, p)?, ep, p)?;
+    let yq = B::mod_exp(&B::rem(&x, q)?, eq, q)?;
+
+    // yp - yq, lifted into [0, p)
+    let d = B::rem(&B::sub(&B::add(&yp, p)?, &B::rem(&yq, p)?)?, p)?;
+    let h = B::mod_mul(&d, q_inv, p)?;
+    B::to_bytes(&B::add(&B::mul(&h, q)?, &yq)?)
+}
+
+pub fn eval_with<B: BigIntBackend>(x: &[u8], n: &B::Int, squarings: u64) -> Result<Vec<u8>> {
+    let g = B::rem(&B::from_bytes(x)?, n)?;
+    B::to_bytes(&B::square_repeated(&g, squarings, n)?)
+}
+
+#[cfg(feature = "openssl")]
+pub fn eval(x: &[u8], n: &BigNum, squarings: u64) -> Result<Vec<u8>> {
+    let mut ctx = BigNumContext::new()?;
+    let x = BigNum::from_slice(x)?;
+    let mut g = BigNum::new()?;
+    g.nnmod(&x, n, &mut ctx)?;
+    Ok(square(&g, squarings, n, &mut ctx)?.to_vec())
+}
+
+/// Squares the reduced `x` `times` times modulo the odd `n`, staying in Montgomery form throughout.
+#[cfg(feature = "openssl")]
+pub fn square(x: &BigNumRef, times: u64, n: &BigNum, ctx: &mut BigNumContext) -> Result<BigNum> {
+    let mont = MontCtx::new(n, ctx)?;
+    let mut g = mont.to_mont(x, ctx)?;
+    for _ in 0..times {
+        mont.square(&mut g, ctx)?;
+    }
+    mont.redc(&g, ctx)
+}
+
+/// Repeated squaring `x -> x^(2^T) mod n` in an RSA group, with the arithmetic of backend `B`.
+///
+/// The output is `±y` in `Z_n^* / {±1}`, given as the representative `min(y, n - y)`, so that a delay proof cannot
+/// vouch for `n - y` as well.
+pub struct RsaSquaring<B: BigIntBackend = DefaultBackend> {
+    n: B::Int,
+    squarings: u64,
+    no_small_order: bool,
+    trapdoor: Option<CrtTrapdoor<B>>,
+    #[cfg(feature = "openssl")]
+    proofs: Option<ProofKind>,
+}
+
+impl RsaSquaring {
+    pub fn new(modulus: &Modulus, squarings: u64) -> Result<Self> {
+        Self::new_in(modulus, squarings)
+    }
+
+    pub fn with_trapdoor(trapdoor: &Trapdoor, squarings: u64) -> Result<Self> {
+        Self::with_trapdoor_in(trapdoor, squarings)
+    }
+}
+
+impl<B: BigIntBackend> RsaSquaring<B> {
+    pub fn new_in(modulus: &Modulus, squarings: u64) -> Result<Self> {
+        check_squarings(squarings)?;
+        Ok(Self {
+            n: B::from_bytes(modulus.as_bytes())?,
+            squarings,
+            no_small_order: modulus.no_small_order(),
+            trapdoor: None,
+            #[cfg(feature = "openssl")]
+            proofs: None,
+        })
+    }
+
+    pub fn with_trapdoor_in(trapdoor: &Trapdoor, squarings: u64) -> Result<Self> {
+        let delay = Self::new_in(&trapdoor.modulus()?, squarings)?;
+        Ok(Self {
+            trapdoor: Some(CrtTrapdoor::new(
+                B::from_bytes(&trapdoor.p)?,
+                B::from_bytes(&trapdoor.q)?,
+                squarings,
+            )?),
+            ..delay
+        })
+    }
+
+    /// Enables [`DelayFunction::prove`] and [`DelayFunction::verify`] with the given proof system, which always runs
+    /// on OpenSSL.
+    #[cfg(feature = "openssl")]
+    pub fn with_proofs(self, kind: ProofKind) -> Self {
+        Self {
+            proofs: Some(kind),
+            ..self
+        }
+    }
+
+    pub fn modulus(&self) -> &B::Int {
+        &self.n
+    }
+
+    pub fn squarings(&self) -> u64 {
+        self.squarings
+    }
+
+    #[cfg(feature = "openssl")]
+    fn proof_kind(&self) -> Result<ProofKind> {
+        self.proofs.ok_or(PostError::Unsupported(
+            "delay proofs without a proof system",
+        ))
+    }
+
+    #[cfg(feature = "openssl")]
+    fn openssl_modulus(&self) -> Result<BigNum> {
+        Ok(BigNum::from_slice(&B::to_bytes(&self.n)?)?)
+    }
+
+    // Squares `x` into `QR_n` if the modulus has no small order.
+    fn input(&self, x: &[u8]) -> Result<Vec<u8>> {
+        if !self.no_small_order {
+            return Ok(x.to_vec());
+        }
+        let g = B::rem(&B::from_bytes(x)?, &self.n)?;
+        B::to_bytes(&B::mod_mul(&g, &g, &self.n)?)
+    }
+
+    // Maps the reduced output `y` to `min(y, n - y)`.
+    fn output(&self, y: Vec<u8>) -> Result<Vec<u8>> {
+        let neg = B::to_bytes(&B::sub(&self.n, &B::from_bytes(&y)?)?)?;
+        Ok(if (neg.len(), &neg) < (y.len(), &y) {
+            neg
+        } else {
+            y
+        })
+    }
+}
+
+impl<B: BigIntBackend> DelayFunction for RsaSquaring<B> {
+    fn eval(&self, x: &[u8]) -> Result<Vec<u8>> {
+        self.output(eval_with::<B>(&self.input(x)?, &self.n, self.squarings)?)
+    }
+
+    fn eval_trapdoor(&self, x: &[u8]) -> Result<Vec<u8>> {
+        let trapdoor = self
+            .trapdoor
+            .as_ref()
+            .ok_or(PostError::Unsupported("evaluation without a trapdoor"))?;
+        self.output(eval_trap(&self.input(x)?, trapdoor)?)
+    }
+
+    #[cfg(feature = "openssl")]
+    fn prove(&self, x: &[u8]) -> Result<(Vec<u8>, Vec<u8>)> {
+        vdf::eval_and_prove(
+            self.proof_kind()?,
+            &self.input(x)?,
+            &self.openssl_modulus()?,
+            self.squarings,
+        )
+    }
+
+    #[cfg(feature = "openssl")]
+    fn verify(&self, x: &[u8], y: &[u8], proof: &[u8]) -> Result<bool> {
+        vdf::verify(
+            self.proof_kind()?,
+            &self.input(x)?,
+            y,
+            proof,
+            &self.openssl_modulus()?,
+            self.squarings,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modulus::ModulusGenerator;
+
+    #[test]
+    fn crt_matches_eval() {
+        for generator in [
+            ModulusGenerator::new(1024),
+            ModulusGenerator::new(1024).no_small_order(),
+        ] {
+            let (modulus, trapdoor) = generator.generate().unwrap();
+            // Inputs above `n` and sharing a factor with it are reduced the same way on both sides.
+            let inputs = [b"x".to_vec(), vec![0xff; 200], trapdoor.p.clone(), vec![]];
+            for squarings in [1, 2, 1000] {
+                let public = RsaSquaring::new(&modulus, squarings).unwrap();
+                let private = RsaSquaring::with_trapdoor(&trapdoor, squarings).unwrap();
+                for x in &inputs {
+                    let y = public.eval(x).unwrap();
+                    assert_eq!(private.eval_trapdoor(x).unwrap(), y);
+                    #[cfg(feature = "openssl")]
+                    vdf::check_signed(&BigNum::from_slice(&y).unwrap(), &modulus.n().unwrap())
+                        .unwrap();
+                }
+                assert!(public.eval_trapdoor(b"x").is_err());
+            }
+        }
+    }
+}
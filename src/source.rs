@@ -0,0 +1,195 @@
+//! Random access to the stored data, so that rounds can hash or sample blocks of files larger than memory.
+//!
+//! Anything that is `AsRef<[u8]>` is a [`Source`], including a `memmap2::Mmap` from [`map`] with the `mmap` feature;
+//! [`Reader`] serves any `Read + Seek`, such as a [`File`].
+
+use std::{
+    fs::File,
+    io::{self, Read, Seek, SeekFrom},
+    path::Path,
+    sync::Mutex,
+};
+
+use crate::error::Result;
+
+// Buffer size when streaming a whole source.
+const CHUNK_BYTES: usize = 1 << 20;
+
+pub trait Source {
+    /// Length of the data in bytes.
+    fn size(&self) -> Result<u64>;
+
+    /// Reads from `offset` until `buf` is full or the data ends, returning the number of bytes read.
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> Result<usize>;
+
+    /// Reads `size` bytes from `offset`, zero-padded past the end of the data.
+    fn read_padded(&self, offset: u64, size: usize) -> Result<Vec<u8>> {
+        let mut buf = vec![0; size];
+        self.read_at(offset, &mut buf)?;
+        Ok(buf)
+    }
+
+    /// Feeds the whole data to `f` in order, in chunks of unspecified size.
+    fn for_each_chunk(&self, f: &mut dyn FnMut(&[u8]) -> Result<()>) -> Result<()> {
+        let mut buf = vec![0; CHUNK_BYTES];
+        let mut offset = 0;
+        loop {
+            let n = self.read_at(offset, &mut buf)?;
+            if n == 0 {
+                return Ok(());
+            }
+            f(&buf[..n])?;
+            offset += n as u64;
+        }
+    }
+}
+
+impl<T: AsRef<[u8]> + ?Sized> Source for T {
+    fn size(&self) -> Result<u64> {
+        Ok(self.as_ref().len() as u64)
+    }
+
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> Result<usize> {
+        let data = self.as_ref();
+        let start = usize::try_from(offset).map_or(data.len(), |o| o.min(data.len()));
+        let n = usize::min(buf.len(), data.len() - start);
+        buf[..n].copy_from_slice(&data[start..start + n]);
+        Ok(n)
+    }
+
+    fn for_each_chunk(&self, f: &mut dyn FnMut(&[u8]) -> Result<()>) -> Result<()> {
+        f(self.as_ref())
+    }
+}
+
+/// A [`Source`] over a seekable stream, which is only read as far as the rounds need.
+pub struct Reader<R> {
+    inner: Mutex<R>,
+    len: u64,
+}
+
+impl<R: Read + Seek> Reader<R> {
+    pub fn new(mut inner: R) -> Result<Self> {
+        let len = inner.seek(SeekFrom::End(0))?;
+        Ok(Self {
+            inner: Mutex::new(inner),
+            len,
+        })
+    }
+}
+
+impl Reader<File> {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        Self::new(File::open(path)?)
+    }
+}
+
+impl<R: Read + Seek> Source for Reader<R> {
+    fn size(&self) -> Result<u64> {
+        Ok(self.len)
+    }
+
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> Result<usize> {
+        // Files refuse to seek beyond `i64::MAX`, so offsets past the end are answered without seeking.
+        if offset >= self.len {
+            return Ok(0);
+        }
+        let mut inner = self
+            .inner
+            .lock()
+            .map_err(|_| io::Error::other("reader poisoned by a panic"))?;
+        inner.seek(SeekFrom::Start(offset))?;
+        let mut n = 0;
+        while n < buf.len() {
+            match inner.read(&mut buf[n..])? {
+                0 => break,
+                m => n += m,
+            }
+        }
+        Ok(n)
+    }
+}
+
+/// Maps the file at `path` into memory.
+///
+/// The file must not be truncated or modified by other processes while the map is alive.
+#[cfg(feature = "mmap")]
+pub fn map(path: impl AsRef<Path>) -> Result<memmap2::Mmap> {
+    let file = File::open(path)?;
+    // SAFETY: the caller keeps the file unchanged while it is mapped, as documented above.
+    Ok(unsafe { memmap2::Mmap::map(&file)? })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    // Longer than two chunks, with no period that lines up with a chunk.
+    fn data() -> Vec<u8> {
+        (0..2 * CHUNK_BYTES + 1000)
+            .map(|i| (i % 251) as u8)
+            .collect()
+    }
+
+    fn chunks<S: Source + ?Sized>(s: &S) -> Vec<u8> {
+        let mut out = vec![];
+        s.for_each_chunk(&mut |chunk| {
+            out.extend_from_slice(chunk);
+            Ok(())
+        })
+        .unwrap();
+        out
+    }
+
+    // Checks `s` against the blanket impl over the same bytes.
+    fn agrees<S: Source + ?Sized>(s: &S, data: &[u8]) {
+        let len = data.len() as u64;
+        assert_eq!(s.size().unwrap(), len);
+
+        let mut buf = vec![0; 100];
+        let straddle = (CHUNK_BYTES - 50) as u64;
+        assert_eq!(s.read_at(straddle, &mut buf).unwrap(), 100);
+        assert_eq!(buf, &data[CHUNK_BYTES - 50..CHUNK_BYTES + 50]);
+
+        assert_eq!(s.read_at(len - 30, &mut buf).unwrap(), 30);
+        assert_eq!(buf[..30], data[data.len() - 30..]);
+        assert_eq!(s.read_at(len, &mut buf).unwrap(), 0);
+        assert_eq!(s.read_at(len + 1000, &mut buf).unwrap(), 0);
+        assert_eq!(s.read_at(u64::MAX, &mut buf).unwrap(), 0);
+
+        let padded = s.read_padded(len - 30, 100).unwrap();
+        assert_eq!(padded[..30], data[data.len() - 30..]);
+        assert!(padded[30..].iter().all(|&b| b == 0));
+        assert_eq!(s.read_padded(len + 1, 10).unwrap(), vec![0; 10]);
+
+        assert_eq!(chunks(s), chunks(data));
+    }
+
+    #[test]
+    fn slices() {
+        let data = data();
+        agrees(&data[..], &data);
+        assert_eq!(chunks(&data[..]), data);
+        assert_eq!(chunks(&[][..]), b"");
+    }
+
+    #[test]
+    fn readers() {
+        let data = data();
+        agrees(&Reader::new(Cursor::new(data.clone())).unwrap(), &data);
+        assert_eq!(chunks(&Reader::new(Cursor::new(vec![])).unwrap()), b"");
+    }
+
+    #[test]
+    fn files() {
+        let data = data();
+        let path = std::env::temp_dir().join(format!("ndss-{}-source", std::process::id()));
+        std::fs::write(&path, &data).unwrap();
+        agrees(&Reader::open(&path).unwrap(), &data);
+        #[cfg(feature = "mmap")]
+        agrees(&map(&path).unwrap(), &data);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
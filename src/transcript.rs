@@ -0,0 +1,244 @@
+//! Per-round record of a chain, for locating the round where a prover and the verifier parted ways.
+//!
+//! A [`Tag`] only keeps the digests of all challenges and all responses, so a mismatch does not tell which round went
+//! wrong. A [`Transcript`] keeps every challenge `c_i`, response `v_i` and optionally delay output `y_i`. Since each
+//! challenge is derived from everything before it, two chains that diverge at some round differ at every later round
+//! too, so the first divergent round can be found with a binary search over the rounds.
+//!
+//! For audits without the whole chain, the prover commits to its transcript with a Merkle root over the round records
+//! and opens the rounds the verifier samples, see [`spot_check`].
+
+use std::collections::BTreeSet;
+
+use crate::{
+    error::{PostError, Result},
+    hash::{Domain, Suite},
+    merkle::{hash_leaf, verify_path, MerkleTree},
+    post::Tag,
+    verify::{Rejection, Verdict},
+};
+
+/// One round of a [`Transcript`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TranscriptRound {
+    pub c: Vec<u8>,
+    pub v: Vec<u8>,
+    /// The delay output on the digest of `v`, if the chain was run with delay outputs.
+    pub y: Option<Vec<u8>>,
+}
+
+impl TranscriptRound {
+    // Delay outputs are only compared when both sides kept them.
+    fn agrees(&self, other: &Self) -> bool {
+        let y = match (&self.y, &other.y) {
+            (Some(a), Some(b)) => a == b,
+            _ => true,
+        };
+        self.c == other.c && self.v == other.v && y
+    }
+}
+
+/// Every round of a chain together with the suite it was hashed with.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Transcript {
+    pub suite: Suite,
+    pub rounds: Vec<TranscriptRound>,
+}
+
+impl Transcript {
+    /// The compact [`Tag`] of the same chain.
+    pub fn tag(&self) -> Result<Tag> {
+        let mut cs = self.suite.hasher(Domain::Transcript)?;
+        let mut vs = self.suite.hasher(Domain::Transcript)?;
+        for round in &self.rounds {
+            cs.update(&round.c)?;
+            vs.update(&round.v)?;
+        }
+        Ok(Tag {
+            cs: cs.finish()?,
+            vs: vs.finish()?,
+            suite: self.suite,
+        })
+    }
+
+    /// The first round at which `self` and `other` disagree, or `None` if they are the same chain.
+    ///
+    /// Transcripts of different lengths diverge at the end of the shorter one; with different suites they diverge
+    /// immediately.
+    pub fn first_divergence(&self, other: &Self) -> Option<usize> {
+        if self.suite != other.suite {
+            return Some(0);
+        }
+        let rounds = usize::min(self.rounds.len(), other.rounds.len());
+        match first_divergence(rounds, |i| Ok(self.rounds[i].agrees(&other.rounds[i]))) {
+            Ok(Some(i)) => Some(i),
+            _ if self.rounds.len() != other.rounds.len() => Some(rounds),
+            _ => None,
+        }
+    }
+}
+
+/// Binary search for the first of `rounds` rounds where `agrees` fails, with `O(log rounds)` calls.
+///
+/// `agrees(i)` compares round `i` of two chains, e.g. a stored transcript with rounds fetched one at a time from a
+/// prover. The search assumes that the chains never agree again once they diverge, which holds for honestly derived
+/// challenges.
+pub fn first_divergence(
+    rounds: usize,
+    mut agrees: impl FnMut(usize) -> Result<bool>,
+) -> Result<Option<usize>> {
+    let (mut lo, mut hi) = (0, rounds);
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+        if agrees(mid)? {
+            lo = mid + 1;
+        } else {
+            hi = mid;
+        }
+    }
+    Ok((lo < rounds).then_some(lo))
+}
+
+// Leaf data of round `i`: the index, the length-prefixed challenge and the response.
+fn record(i: usize, c: &[u8], v: &[u8]) -> Vec<u8> {
+    [
+        &(i as u64).to_be_bytes()[..],
+        &(c.len() as u32).to_be_bytes(),
+        c,
+        v,
+    ]
+    .concat()
+}
+
+/// The prover's final response in place of a [`Tag`]: the Merkle root over the records `(i, c_i, v_i)` of all rounds.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TranscriptCommitment {
+    pub root: Vec<u8>,
+    pub rounds: usize,
+    pub suite: Suite,
+}
+
+/// A round record with its authentication path under a [`TranscriptCommitment`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RoundOpening {
+    pub round: usize,
+    pub c: Vec<u8>,
+    pub v: Vec<u8>,
+    pub path: Vec<Vec<u8>>,
+}
+
+/// A [`Transcript`] together with the Merkle tree over its rounds, kept by the prover to answer audits.
+pub struct CommittedTranscript {
+    transcript: Transcript,
+    tree: MerkleTree,
+}
+
+impl Transcript {
+    pub fn commit(self) -> Result<CommittedTranscript> {
+        let leaves = self
+            .rounds
+            .iter()
+            .enumerate()
+            .map(|(i, r)| hash_leaf(&record(i, &r.c, &r.v)))
+            .collect::<Result<_>>()?;
+        Ok(CommittedTranscript {
+            tree: MerkleTree::new(leaves)?,
+            transcript: self,
+        })
+    }
+}
+
+impl CommittedTranscript {
+    pub fn commitment(&self) -> TranscriptCommitment {
+        TranscriptCommitment {
+            root: self.tree.root().to_vec(),
+            rounds: self.tree.leaves(),
+            suite: self.transcript.suite,
+        }
+    }
+
+    pub fn transcript(&self) -> &Transcript {
+        &self.transcript
+    }
+
+    /// Opens round `i`, or fails if the chain has no such round.
+    pub fn open(&self, i: usize) -> Result<RoundOpening> {
+        let r = self.transcript.rounds.get(i).ok_or_else(|| {
+            PostError::InvalidParameters(format!(
+                "round {} of a chain of {}",
+                i,
+                self.transcript.rounds.len()
+            ))
+        })?;
+        Ok(RoundOpening {
+            round: i,
+            c: r.c.clone(),
+            v: r.v.clone(),
+            path: self.tree.path(i).into_iter().map(<[u8]>::to_vec).collect(),
+        })
+    }
+}
+
+impl TranscriptCommitment {
+    /// Checks that `opening` is the round it claims to be under this root.
+    pub fn check(&self, opening: &RoundOpening) -> Result<bool> {
+        let depth = self.rounds.next_power_of_two().trailing_zeros() as usize;
+        if opening.round >= self.rounds || opening.path.len() != depth {
+            return Ok(false);
+        }
+        let leaf = hash_leaf(&record(opening.round, &opening.c, &opening.v))?;
+        let path = opening.path.iter().map(Vec::as_slice).collect::<Vec<_>>();
+        verify_path(&self.root, opening.round, &leaf, &path)
+    }
+}
+
+/// Draws `count` distinct rounds out of `rounds` to open, or all of them if there are fewer.
+///
+/// The verifier must only draw them after receiving the [`TranscriptCommitment`], or the prover could answer just those.
+pub fn sample_rounds(rounds: usize, count: usize) -> Result<Vec<usize>> {
+    let mut sample = BTreeSet::new();
+    let mut buf = [0; 8];
+    while sample.len() < count.min(rounds) {
+        getrandom::getrandom(&mut buf)?;
+        sample.insert((u64::from_be_bytes(buf) % rounds as u64) as usize);
+    }
+    Ok(sample.into_iter().collect())
+}
+
+/// Spot-checks a prover's commitment against the verifier's own `expected` transcript, computed cheaply with the
+/// trapdoor in [`Store::run_with_transcript`](crate::Store::run_with_transcript).
+///
+/// `requested` are the rounds the verifier drew with [`sample_rounds`], and `openings` must answer exactly those, in the
+/// same order. Every opening must verify under the commitment and match the verifier's record of the same round. A
+/// chain that is wrong in a fraction `f` of its rounds passes `l` random openings with probability about `(1 - f)^l`.
+pub fn spot_check(
+    expected: &Transcript,
+    commitment: &TranscriptCommitment,
+    requested: &[usize],
+    openings: &[RoundOpening],
+) -> Result<Verdict> {
+    if commitment.rounds != expected.rounds.len()
+        || openings.len() != requested.len()
+        || openings.iter().zip(requested).any(|(o, &i)| o.round != i)
+    {
+        return Ok(Verdict::Rejected(Rejection::Malformed));
+    }
+    if commitment.suite != expected.suite {
+        return Ok(Verdict::Rejected(Rejection::SuiteMismatch));
+    }
+    for opening in openings {
+        if !commitment.check(opening)? {
+            return Ok(Verdict::Rejected(Rejection::Malformed));
+        }
+        let r = &expected.rounds[opening.round];
+        if r.c != opening.c {
+            return Ok(Verdict::Rejected(Rejection::ChallengeMismatch));
+        }
+        if r.v != opening.v {
+            return Ok(Verdict::Rejected(Rejection::RoundResponse {
+                round: opening.round,
+            }));
+        }
+    }
+    Ok(Verdict::Accepted)
+}
@@ -0,0 +1,136 @@
+//! Whole-file round over a keyed tree hash whose leaves are hashed once.
+//!
+//! [`FileMac`](crate::FileMac) hashes the whole file under every round key, so `k` rounds cost `k` passes over the
+//! file. Here the file is cut into leaves whose digests are computed once, and the response to a challenge `c` is the
+//! MAC under `c` of the file length, every leaf digest and `l` leaves sampled by `c`. It still depends on every byte of
+//! the file and cannot be computed before `c` is known, but a round only hashes `32` bytes per leaf plus the samples.
+//!
+//! The leaf digests alone are a compressed copy of the file, so it is the raw samples that force the prover to keep the
+//! data: if a fraction `f` of the leaves is lost, a round still goes through with probability `(1 - f)^l`, as with
+//! [`merkle_por`](crate::merkle_por).
+
+use crate::{
+    error::{PostError, Result},
+    hash::{Domain, Suite},
+    merkle::hash_leaf,
+    round::{sample, Round},
+    source::Source,
+};
+
+fn leaf<S: Source + ?Sized>(d: &S, leaf_size: usize, i: usize) -> Result<Vec<u8>> {
+    let mut buf = vec![0; leaf_size];
+    let n = d.read_at((i * leaf_size) as u64, &mut buf)?;
+    buf.truncate(n);
+    Ok(buf)
+}
+
+/// Expands a round challenge into the indices of `l` sampled leaves with the chain's `suite`.
+pub fn challenge(suite: Suite, c: &[u8], leaves: usize, l: usize) -> Result<Vec<usize>> {
+    sample(suite, c, b"tree-mac-index", leaves, l)
+}
+
+/// Round function keyed over precomputed leaf digests and `l` sampled leaves of the file.
+pub struct TreeMac<'a, S: ?Sized = [u8]> {
+    d: &'a S,
+    len: u64,
+    leaf_size: usize,
+    // The leaf digests, concatenated.
+    digests: Vec<u8>,
+    l: usize,
+}
+
+impl<'a, S: Source + ?Sized> TreeMac<'a, S> {
+    /// Hashes the file in leaves of `leaf_size` bytes, keeping 32 bytes per leaf, and samples `l` leaves per round.
+    pub fn new(d: &'a S, leaf_size: usize, l: usize) -> Result<Self> {
+        if leaf_size == 0 || l == 0 {
+            return Err(PostError::InvalidParameters(
+                "leaf size and samples must be positive".into(),
+            ));
+        }
+        let len = d.size()?;
+        if len == 0 {
+            return Err(PostError::InvalidParameters(
+                "cannot hash an empty file into leaves".into(),
+            ));
+        }
+        let mut digests = vec![];
+        for i in 0..len.div_ceil(leaf_size as u64) as usize {
+            digests.extend_from_slice(&hash_leaf(&leaf(d, leaf_size, i)?)?);
+        }
+        Ok(Self {
+            d,
+            len,
+            leaf_size,
+            digests,
+            l,
+        })
+    }
+
+    pub fn leaves(&self) -> usize {
+        self.len.div_ceil(self.leaf_size as u64) as usize
+    }
+}
+
+impl<S: Source + ?Sized> Round for TreeMac<'_, S> {
+    fn respond(&self, suite: Suite, c: &[u8]) -> Result<Vec<u8>> {
+        let mut h = suite.keyed(Domain::RoundMac, c)?;
+        h.update(b"tree-mac")?;
+        h.update(&self.len.to_be_bytes())?;
+        h.update(&self.digests)?;
+        for i in challenge(suite, c, self.leaves(), self.l)? {
+            h.update(&leaf(self.d, self.leaf_size, i)?)?;
+        }
+        h.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn samples_with_the_suite() {
+        let d = (0..1000).map(|i| (i * 7) as u8).collect::<Vec<_>>();
+        let round = TreeMac::new(&d[..], 16, 8).unwrap();
+        assert_ne!(
+            challenge(Suite::Kmac256, b"c", round.leaves(), 8).unwrap(),
+            challenge(Suite::Blake3, b"c", round.leaves(), 8).unwrap()
+        );
+        for suite in Suite::ALL {
+            let v = round.respond(suite, b"c").unwrap();
+            assert_eq!(v.len(), 32);
+            assert_ne!(round.respond(suite, b"other").unwrap(), v);
+        }
+        assert!(challenge(Suite::default(), b"c", 0, 8).is_err());
+    }
+
+    #[test]
+    fn depends_on_every_leaf() {
+        let d = (0..1000).map(|i| (i * 7) as u8).collect::<Vec<_>>();
+        let round = TreeMac::new(&d[..], 16, 4).unwrap();
+        let v = round.respond(Suite::default(), b"c").unwrap();
+        let sampled = challenge(Suite::default(), b"c", round.leaves(), 4).unwrap();
+
+        // An unsampled leaf is only covered by its digest.
+        let unsampled = (0..round.leaves()).find(|i| !sampled.contains(i)).unwrap();
+        let mut corrupted = d.clone();
+        corrupted[unsampled * 16] ^= 1;
+        let other = TreeMac::new(&corrupted[..], 16, 4).unwrap();
+        assert_ne!(other.respond(Suite::default(), b"c").unwrap(), v);
+
+        // A prover that kept the digests but lost a sampled leaf is caught by the raw sample.
+        let mut corrupted = d.clone();
+        corrupted[sampled[0] * 16] ^= 1;
+        let lossy = TreeMac {
+            digests: round.digests.clone(),
+            ..TreeMac::new(&corrupted[..], 16, 4).unwrap()
+        };
+        assert_ne!(lossy.respond(Suite::default(), b"c").unwrap(), v);
+        assert_eq!(
+            TreeMac { d: &d[..], ..lossy }
+                .respond(Suite::default(), b"c")
+                .unwrap(),
+            v
+        );
+    }
+}
@@ -0,0 +1,299 @@
+//! Proofs that `y = x^(2^T)` was computed correctly, checkable without the trapdoor.
+
+pub mod pietrzak;
+pub mod wesolowski;
+
+use std::cell::RefCell;
+
+use openssl::bn::{BigNum, BigNumContext, BigNumRef};
+
+use crate::{
+    error::{PostError, Result},
+    hash::{Domain, FIXED_SUITE},
+    rsa::eval,
+};
+
+/// A group of unknown order in which proofs of exponentiation are computed.
+pub trait Group {
+    type Element: PartialEq;
+
+    fn identity(&self) -> Result<Self::Element>;
+
+    fn mul(&self, a: &Self::Element, b: &Self::Element) -> Result<Self::Element>;
+
+    fn square(&self, a: &Self::Element) -> Result<Self::Element> {
+        self.mul(a, a)
+    }
+
+    fn pow(&self, a: &Self::Element, e: &BigNumRef) -> Result<Self::Element> {
+        let mut r = self.identity()?;
+        for i in (0..e.num_bits()).rev() {
+            r = self.square(&r)?;
+            if e.is_bit_set(i) {
+                r = self.mul(&r, a)?;
+            }
+        }
+        Ok(r)
+    }
+
+    fn encode(&self, a: &Self::Element) -> Result<Vec<u8>>;
+
+    /// Parses an element, rejecting encodings that are not canonical.
+    fn decode(&self, bytes: &[u8]) -> Result<Self::Element>;
+}
+
+/// The multiplicative group modulo an RSA modulus, up to sign.
+///
+/// Every element stands for `±a` and is kept as the representative `min(a, n - a)`: `-1` is a square root of unity that
+/// anyone knows, so `Z_n^*` itself would let a prover flip the sign of an output together with its proof.
+pub struct RsaGroup<'a> {
+    n: &'a BigNum,
+    ctx: RefCell<BigNumContext>,
+}
+
+impl<'a> RsaGroup<'a> {
+    pub fn new(n: &'a BigNum) -> Result<Self> {
+        Ok(Self {
+            n,
+            ctx: RefCell::new(BigNumContext::new()?),
+        })
+    }
+}
+
+impl Group for RsaGroup<'_> {
+    type Element = BigNum;
+
+    fn identity(&self) -> Result<BigNum> {
+        Ok(BigNum::from_u32(1)?)
+    }
+
+    fn mul(&self, a: &BigNum, b: &BigNum) -> Result<BigNum> {
+        let mut r = BigNum::new()?;
+        r.mod_mul(a, b, self.n, &mut self.ctx.borrow_mut())?;
+        signed(r, self.n)
+    }
+
+    fn square(&self, a: &BigNum) -> Result<BigNum> {
+        let mut r = BigNum::new()?;
+        r.mod_sqr(a, self.n, &mut self.ctx.borrow_mut())?;
+        signed(r, self.n)
+    }
+
+    fn pow(&self, a: &BigNum, e: &BigNumRef) -> Result<BigNum> {
+        let mut r = BigNum::new()?;
+        r.mod_exp(a, e, self.n, &mut self.ctx.borrow_mut())?;
+        signed(r, self.n)
+    }
+
+    fn encode(&self, a: &BigNum) -> Result<Vec<u8>> {
+        Ok(a.to_vec())
+    }
+
+    fn decode(&self, bytes: &[u8]) -> Result<BigNum> {
+        canonical(bytes, self.n)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProofKind {
+    /// A single group element, but the prover repeats all `T` squarings.
+    Wesolowski,
+    /// `ceil(log2 T)` group elements, with a prover overhead of about `T / 1024` squarings.
+    Pietrzak,
+}
+
+/// Evaluates the delay function on `x` and proves the result.
+pub fn eval_and_prove(
+    kind: ProofKind,
+    x: &[u8],
+    n: &BigNum,
+    squarings: u64,
+) -> Result<(Vec<u8>, Vec<u8>)> {
+    match kind {
+        ProofKind::Wesolowski => {
+            let group = RsaGroup::new(n)?;
+            let mut ctx = BigNumContext::new()?;
+            let y = element(&eval(x, n, squarings)?, n, &mut ctx)?;
+            let proof = wesolowski::prove(&group, &element(x, n, &mut ctx)?, &y, squarings)?;
+            Ok((y.to_vec(), proof))
+        }
+        ProofKind::Pietrzak => pietrzak::eval_and_prove(x, n, squarings),
+    }
+}
+
+pub fn verify(
+    kind: ProofKind,
+    x: &[u8],
+    y: &[u8],
+    proof: &[u8],
+    n: &BigNum,
+    squarings: u64,
+) -> Result<bool> {
+    match kind {
+        ProofKind::Wesolowski => {
+            let group = RsaGroup::new(n)?;
+            let mut ctx = BigNumContext::new()?;
+            wesolowski::verify(
+                &group,
+                &element(x, n, &mut ctx)?,
+                &group.decode(y)?,
+                proof,
+                squarings,
+            )
+        }
+        ProofKind::Pietrzak => pietrzak::verify(x, y, proof, n, squarings),
+    }
+}
+
+/// Fiat-Shamir challenge: the first prime of `bits` bits (at most 127) found by hashing `parts` with a counter.
+pub fn hash_to_prime(parts: &[&[u8]], bits: usize) -> Result<BigNum> {
+    let mut ctx = BigNumContext::new()?;
+    for counter in 0u64.. {
+        let mut h = FIXED_SUITE.hash(
+            Domain::ProofChallenge,
+            &[parts.concat(), counter.to_be_bytes().to_vec()].concat(),
+        )?;
+        h.truncate(bits.div_ceil(8));
+        h[0] &= 0xff >> (h.len() * 8 - bits);
+        let mut l = BigNum::from_slice(&h)?;
+        l.set_bit(bits as i32 - 1)?;
+        l.set_bit(0)?;
+        if l.is_prime(64, &mut ctx)? {
+            return Ok(l);
+        }
+    }
+    unreachable!()
+}
+
+// Parses the claimed output of the delay function. The next round challenge is derived from its bytes, so any other
+// encoding of the same element, e.g. `y + n`, `n - y` or one with leading zeros, would let the prover pick among
+// challenges.
+pub(crate) fn canonical(y: &[u8], n: &BigNum) -> Result<BigNum> {
+    let a = BigNum::from_slice(y)?;
+    check_signed(&a, n)?;
+    if a.to_vec() != y {
+        return Err(PostError::MalformedProof(
+            "group element not minimally encoded".into(),
+        ));
+    }
+    Ok(a)
+}
+
+// Rejects anything but the representative `min(a, n - a)` of `±a`.
+pub(crate) fn check_signed(a: &BigNumRef, n: &BigNumRef) -> Result<()> {
+    if a >= n {
+        return Err(PostError::MalformedProof(
+            "group element out of range".into(),
+        ));
+    }
+    let mut neg = BigNum::new()?;
+    neg.checked_sub(n, a)?;
+    if neg < *a {
+        return Err(PostError::MalformedProof(
+            "group element not in signed form".into(),
+        ));
+    }
+    Ok(())
+}
+
+// The representative `min(a, n - a)` of `±a`, for a reduced `a`.
+pub(crate) fn signed(a: BigNum, n: &BigNumRef) -> Result<BigNum> {
+    let mut neg = BigNum::new()?;
+    neg.checked_sub(n, &a)?;
+    Ok(if neg < a { neg } else { a })
+}
+
+// Reduces the byte encoding of a group element to its signed representative.
+pub(crate) fn element(x: &[u8], n: &BigNum, ctx: &mut BigNumContext) -> Result<BigNum> {
+    let x = BigNum::from_slice(x)?;
+    let mut r = BigNum::new()?;
+    r.nnmod(&x, n, ctx)?;
+    signed(r, n)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const X: &[u8] = b"delay input";
+
+    pub(super) fn modulus() -> BigNum {
+        let mut ctx = BigNumContext::new().unwrap();
+        let mut p = BigNum::new().unwrap();
+        p.generate_prime(512, false, None, None).unwrap();
+        let mut q = BigNum::new().unwrap();
+        q.generate_prime(512, false, None, None).unwrap();
+        let mut n = BigNum::new().unwrap();
+        n.checked_mul(&p, &q, &mut ctx).unwrap();
+        n
+    }
+
+    #[test]
+    fn wesolowski_roundtrip() {
+        let n = modulus();
+        for squarings in [1, 2, 100, 1000] {
+            let (y, proof) = eval_and_prove(ProofKind::Wesolowski, X, &n, squarings).unwrap();
+            let mut ctx = BigNumContext::new().unwrap();
+            let expected = element(&eval(X, &n, squarings).unwrap(), &n, &mut ctx).unwrap();
+            assert_eq!(y, expected.to_vec());
+            assert!(verify(ProofKind::Wesolowski, X, &y, &proof, &n, squarings).unwrap());
+            assert!(!verify(ProofKind::Wesolowski, X, &y, &proof, &n, squarings + 1).unwrap());
+            assert!(!verify(ProofKind::Wesolowski, b"other", &y, &proof, &n, squarings).unwrap());
+
+            let mut wrong = y.clone();
+            *wrong.last_mut().unwrap() ^= 1;
+            assert!(!verify(ProofKind::Wesolowski, X, &wrong, &proof, &n, squarings).unwrap());
+            // For tiny T the proof is a tiny element, which tampering may leave non-canonical.
+            let mut forged = proof.clone();
+            *forged.last_mut().unwrap() ^= 1;
+            assert!(!verify(ProofKind::Wesolowski, X, &y, &forged, &n, squarings).unwrap_or(false));
+        }
+    }
+
+    #[test]
+    fn rejects_non_canonical_outputs() {
+        let n = modulus();
+        for kind in [ProofKind::Wesolowski, ProofKind::Pietrzak] {
+            for squarings in [1, 7, 64, 1000] {
+                let (y, proof) = eval_and_prove(kind, X, &n, squarings).unwrap();
+                assert!(verify(kind, X, &y, &proof, &n, squarings).unwrap());
+
+                // y + n and y with a leading zero are the same group element as y, but not the same bytes.
+                let mut shifted = BigNum::new().unwrap();
+                shifted
+                    .checked_add(&BigNum::from_slice(&y).unwrap(), &n)
+                    .unwrap();
+                let padded = [&[0][..], &y].concat();
+                for other in [shifted.to_vec(), padded] {
+                    assert!(matches!(
+                        verify(kind, X, &other, &proof, &n, squarings),
+                        Err(PostError::MalformedProof(_))
+                    ));
+                }
+            }
+        }
+    }
+
+    fn negate(a: &[u8], n: &BigNum) -> Vec<u8> {
+        let mut r = BigNum::new().unwrap();
+        r.checked_sub(n, &BigNum::from_slice(a).unwrap()).unwrap();
+        r.to_vec()
+    }
+
+    #[test]
+    fn rejects_negated_outputs() {
+        let n = modulus();
+        for squarings in [7, 1000, 1001] {
+            let (y, proof) = eval_and_prove(ProofKind::Wesolowski, X, &n, squarings).unwrap();
+            assert!(verify(ProofKind::Wesolowski, X, &y, &proof, &n, squarings).unwrap());
+            // (n - y, n - π) satisfies π^l x^r = y just as well, so only one sign may be accepted.
+            let (neg_y, neg_proof) = (negate(&y, &n), negate(&proof, &n));
+            for (y, proof) in [(&neg_y, &neg_proof), (&neg_y, &proof), (&y, &neg_proof)] {
+                assert!(matches!(
+                    verify(ProofKind::Wesolowski, X, y, proof, &n, squarings),
+                    Err(PostError::MalformedProof(_))
+                ));
+            }
+        }
+    }
+}
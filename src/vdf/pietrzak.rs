@@ -0,0 +1,229 @@
+//! Pietrzak's recursive-halving proof of exponentiation `y = x^(2^T)`.
+//!
+//! Each halving step sends the midpoint `μ = x^(2^(T/2))`, derives `r = H(x, y, μ)` and folds the claim into
+//! `(x^r μ, μ^r y)` over `T/2` squarings, until `y = x^2` can be checked directly. An odd `T` is first rounded up by
+//! squaring `y`. The proof is `ceil(log2 T)` group elements and verification costs as many short exponentiations.
+//!
+//! As with Wesolowski's proof, `y` and every midpoint are elements of `Z_n^* / {±1}` and must be given as their
+//! representatives `min(a, n - a)`.
+//!
+//! The prover records the powers `x^(2^i)` that the first `d` midpoints are products of while squaring; the remaining
+//! midpoints are recomputed by squaring, which costs about `T / 2^d` extra squarings.
+
+use std::collections::BTreeMap;
+
+use openssl::bn::{BigNum, BigNumContext};
+
+use crate::{
+    error::{PostError, Result},
+    hash::{Domain, FIXED_SUITE},
+    rsa::square,
+    vdf::{canonical, check_signed, element, signed},
+};
+
+const CHECKPOINT_DEPTH: usize = 10;
+
+fn challenge(x: &BigNum, y: &BigNum, mu: &BigNum, t: u64) -> Result<BigNum> {
+    let h = FIXED_SUITE.hash(
+        Domain::ProofChallenge,
+        &[
+            b"pietrzak".as_ref(),
+            &x.to_vec(),
+            &y.to_vec(),
+            &mu.to_vec(),
+            &t.to_be_bytes(),
+        ]
+        .concat(),
+    )?;
+    Ok(BigNum::from_slice(&h[..16])?)
+}
+
+// (x, y) <- (x^r μ, μ^r y)
+fn fold(
+    x: &mut BigNum,
+    y: &mut BigNum,
+    mu: &BigNum,
+    r: &BigNum,
+    n: &BigNum,
+    ctx: &mut BigNumContext,
+) -> Result<()> {
+    let mut a = BigNum::new()?;
+    a.mod_exp(x, r, n, ctx)?;
+    x.mod_mul(&a, mu, n, ctx)?;
+    a.mod_exp(mu, r, n, ctx)?;
+    let mut b = BigNum::new()?;
+    b.mod_mul(&a, y, n, ctx)?;
+    *y = b;
+    Ok(())
+}
+
+// The squaring count of every halving step, before rounding it up to even.
+fn steps(squarings: u64) -> Vec<u64> {
+    let mut steps = vec![];
+    let mut t = squarings;
+    while t > 1 {
+        steps.push(t);
+        t = t.div_ceil(2);
+    }
+    steps
+}
+
+/// Evaluates `y = x^(2^T) mod n` and proves it in one pass over the squaring chain.
+pub fn eval_and_prove(x: &[u8], n: &BigNum, squarings: u64) -> Result<(Vec<u8>, Vec<u8>)> {
+    let mut ctx = BigNumContext::new()?;
+    let steps = steps(squarings);
+    let d = usize::min(steps.len(), CHECKPOINT_DEPTH);
+
+    // Positions i of the x^(2^i) making up the current x, which do not depend on the challenges.
+    let mut positions = vec![0];
+    let mut needed = vec![squarings];
+    for &t in &steps[..d] {
+        let h = t.div_ceil(2);
+        needed.extend(positions.iter().map(|p| p + h));
+        positions = positions.iter().flat_map(|&p| [p, p + h]).collect();
+    }
+    needed.sort_unstable();
+    needed.dedup();
+
+    let x = element(x, n, &mut ctx)?;
+    let mut checkpoints = BTreeMap::new();
+    let (mut g, mut i) = (x.to_owned()?, 0);
+    for p in needed {
+        g = square(&g, p - i, n, &mut ctx)?;
+        checkpoints.insert(p, g.to_owned()?);
+        i = p;
+    }
+    let y = signed(checkpoints[&squarings].to_owned()?, n)?;
+
+    let element_bytes = n.num_bytes();
+    let mut proof = vec![];
+    // The exponent of each x^(2^i) in the current x.
+    let mut terms = vec![(0, BigNum::from_u32(1)?)];
+    let (mut xi, mut yi) = (x, y.to_owned()?);
+    for (i, &t) in steps.iter().enumerate() {
+        if t % 2 == 1 {
+            let mut y2 = BigNum::new()?;
+            y2.mod_sqr(&yi, n, &mut ctx)?;
+            yi = y2;
+        }
+        let h = t.div_ceil(2);
+        let mu = if i < d {
+            let mut mu = BigNum::from_u32(1)?;
+            let mut a = BigNum::new()?;
+            for (p, e) in &terms {
+                a.mod_exp(&checkpoints[&(p + h)], e, n, &mut ctx)?;
+                let mut b = BigNum::new()?;
+                b.mod_mul(&mu, &a, n, &mut ctx)?;
+                mu = b;
+            }
+            signed(mu, n)?
+        } else {
+            signed(square(&xi, h, n, &mut ctx)?, n)?
+        };
+        let r = challenge(&xi, &yi, &mu, t)?;
+        proof.extend_from_slice(&mu.to_vec_padded(element_bytes)?);
+        fold(&mut xi, &mut yi, &mu, &r, n, &mut ctx)?;
+        if i + 1 < d {
+            let mut next = vec![];
+            for (p, e) in terms {
+                let mut er = BigNum::new()?;
+                er.checked_mul(&e, &r, &mut ctx)?;
+                next.push((p, er));
+                next.push((p + h, e));
+            }
+            terms = next;
+        }
+    }
+    Ok((y.to_vec(), proof))
+}
+
+pub fn verify(x: &[u8], y: &[u8], proof: &[u8], n: &BigNum, squarings: u64) -> Result<bool> {
+    let mut ctx = BigNumContext::new()?;
+    let element_bytes = n.num_bytes() as usize;
+    let steps = steps(squarings);
+    if proof.len() != steps.len() * element_bytes {
+        return Err(PostError::MalformedProof(
+            "unexpected Pietrzak proof length".into(),
+        ));
+    }
+    let mut x = element(x, n, &mut ctx)?;
+    let mut y = canonical(y, n)?;
+    for (mu, &t) in proof.chunks(element_bytes).zip(&steps) {
+        let mu = BigNum::from_slice(mu)?;
+        // Like `y`, each midpoint is only determined up to sign.
+        check_signed(&mu, n)?;
+        if t % 2 == 1 {
+            let mut y2 = BigNum::new()?;
+            y2.mod_sqr(&y, n, &mut ctx)?;
+            y = y2;
+        }
+        let r = challenge(&x, &y, &mu, t)?;
+        fold(&mut x, &mut y, &mu, &r, n, &mut ctx)?;
+    }
+    let mut x2 = BigNum::new()?;
+    x2.mod_sqr(&x, n, &mut ctx)?;
+    // The claim is about `±y`, so the folded one holds up to sign.
+    Ok(signed(x2, n)? == signed(y, n)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{rsa::eval, vdf::tests::modulus};
+
+    #[test]
+    fn roundtrip() {
+        let n = modulus();
+        // Odd and non-power-of-two counts, and more halving steps than checkpointed ones.
+        for squarings in [1, 2, 3, 5, 7, 64, 100, 1000, 1025, 5000] {
+            let (y, proof) = eval_and_prove(b"x", &n, squarings).unwrap();
+            let mut ctx = BigNumContext::new().unwrap();
+            let expected = element(&eval(b"x", &n, squarings).unwrap(), &n, &mut ctx).unwrap();
+            assert_eq!(y, expected.to_vec());
+            assert_eq!(proof.len(), steps(squarings).len() * n.num_bytes() as usize);
+            assert!(verify(b"x", &y, &proof, &n, squarings).unwrap());
+            assert!(!verify(b"other", &y, &proof, &n, squarings).unwrap());
+        }
+    }
+
+    fn negate(a: &[u8], n: &BigNum) -> Vec<u8> {
+        let mut r = BigNum::new().unwrap();
+        r.checked_sub(n, &BigNum::from_slice(a).unwrap()).unwrap();
+        r.to_vec_padded(n.num_bytes()).unwrap()
+    }
+
+    #[test]
+    fn rejects_tampering() {
+        let n = modulus();
+        let element_bytes = n.num_bytes() as usize;
+        for squarings in [7, 1000, 1001] {
+            let (y, proof) = eval_and_prove(b"x", &n, squarings).unwrap();
+            let mut wrong = y.clone();
+            *wrong.last_mut().unwrap() ^= 1;
+            assert!(!verify(b"x", &wrong, &proof, &n, squarings).unwrap_or(false));
+            // For odd T, y is squared before the first fold, which used to hide its sign.
+            assert!(matches!(
+                verify(b"x", &negate(&y, &n), &proof, &n, squarings),
+                Err(PostError::MalformedProof(_))
+            ));
+            for i in 0..steps(squarings).len() {
+                let mut forged = proof.clone();
+                let mu = &mut forged[i * element_bytes..(i + 1) * element_bytes];
+                let neg_mu = negate(mu, &n);
+                mu.copy_from_slice(&neg_mu);
+                assert!(matches!(
+                    verify(b"x", &y, &forged, &n, squarings),
+                    Err(PostError::MalformedProof(_))
+                ));
+            }
+            for i in [element_bytes - 1, proof.len() - 1] {
+                let mut forged = proof.clone();
+                forged[i] ^= 1;
+                assert!(!verify(b"x", &y, &forged, &n, squarings).unwrap());
+            }
+            assert!(verify(b"x", &y, &proof[element_bytes..], &n, squarings).is_err());
+            // The proof for one more squaring has the same length for these counts.
+            assert!(!verify(b"x", &y, &proof, &n, squarings + 1).unwrap());
+        }
+    }
+}
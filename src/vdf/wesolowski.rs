@@ -0,0 +1,65 @@
+//! Wesolowski's proof of exponentiation: for `T` squarings and a challenge prime `l = H(x, y)`, the proof is
+//! `π = x^floor(2^T / l)`, and the verifier checks `π^l * x^(2^T mod l) = y` with two short exponentiations.
+
+use openssl::bn::{BigNum, BigNumContext};
+
+use crate::{
+    error::Result,
+    vdf::{hash_to_prime, Group},
+};
+
+const PRIME_BITS: usize = 127;
+
+fn challenge<G: Group>(group: &G, x: &G::Element, y: &G::Element) -> Result<BigNum> {
+    hash_to_prime(
+        &[b"wesolowski", &group.encode(x)?, &group.encode(y)?],
+        PRIME_BITS,
+    )
+}
+
+/// Computes `π` by long division of `2^T` by `l`, costing another `T` sequential squarings.
+pub fn prove<G: Group>(
+    group: &G,
+    x: &G::Element,
+    y: &G::Element,
+    squarings: u64,
+) -> Result<Vec<u8>> {
+    let l = u128::from_be_bytes(
+        challenge(group, x, y)?
+            .to_vec_padded(16)?
+            .try_into()
+            .unwrap(),
+    );
+    let mut pi = group.identity()?;
+    // Invariant: r = 2^i mod l, and it stays below 2^127 so that doubling it cannot overflow.
+    let mut r = 1u128;
+    for _ in 0..squarings {
+        pi = group.square(&pi)?;
+        r <<= 1;
+        if r >= l {
+            r -= l;
+            pi = group.mul(&pi, x)?;
+        }
+    }
+    group.encode(&pi)
+}
+
+pub fn verify<G: Group>(
+    group: &G,
+    x: &G::Element,
+    y: &G::Element,
+    proof: &[u8],
+    squarings: u64,
+) -> Result<bool> {
+    let mut ctx = BigNumContext::new()?;
+    let pi = group.decode(proof)?;
+    let l = challenge(group, x, y)?;
+
+    let two = BigNum::from_u32(2)?;
+    let big_t = BigNum::from_slice(&squarings.to_be_bytes())?;
+    let mut r = BigNum::new()?;
+    r.mod_exp(&two, &big_t, &l, &mut ctx)?;
+
+    let lhs = group.mul(&group.pow(&pi, &l)?, &group.pow(x, &r)?)?;
+    Ok(lhs == *y)
+}
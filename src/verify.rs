@@ -0,0 +1,255 @@
+use std::hint::black_box;
+
+use crate::{
+    delay::DelayFunction,
+    error::{PostError, Result},
+    hash::{Domain, Suite},
+    post::{ChainProof, Response, RoundProof, Tag},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Verdict {
+    Accepted,
+    Rejected(Rejection),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rejection {
+    /// The response digests do not have the length of the tag digests.
+    Malformed,
+    /// The challenge chain diverged, i.e. some round was answered wrongly or late in the chain.
+    ChallengeMismatch,
+    /// The challenges agree but the round responses do not.
+    ResponseMismatch,
+    /// The response was computed with another hash suite than the tag.
+    SuiteMismatch,
+    /// The delay proof of the given round does not verify.
+    DelayProof { round: usize },
+    /// The given round response is not a valid answer to its challenge.
+    RoundResponse { round: usize },
+}
+
+impl Verdict {
+    pub fn is_accepted(&self) -> bool {
+        *self == Verdict::Accepted
+    }
+
+    pub fn into_result(self) -> Result<()> {
+        match self {
+            Verdict::Accepted => Ok(()),
+            Verdict::Rejected(Rejection::Malformed) => {
+                Err(PostError::MalformedProof("unexpected digest length".into()))
+            }
+            Verdict::Rejected(_) => Err(PostError::VerificationFailed),
+        }
+    }
+}
+
+/// Compares the prover's response with the verifier's tag in constant time.
+pub fn verify(tag: &Tag, response: &Response) -> Verdict {
+    if response.cs.len() != tag.cs.len() || response.vs.len() != tag.vs.len() {
+        return Verdict::Rejected(Rejection::Malformed);
+    }
+    if response.suite != tag.suite {
+        return Verdict::Rejected(Rejection::SuiteMismatch);
+    }
+    // Both digests are always compared so that timing does not reveal which one differs.
+    let cs = constant_time_eq(&tag.cs, &response.cs);
+    let vs = constant_time_eq(&tag.vs, &response.vs);
+    match (cs, vs) {
+        (true, true) => Verdict::Accepted,
+        (false, _) => Verdict::Rejected(Rejection::ChallengeMismatch),
+        (true, false) => Verdict::Rejected(Rejection::ResponseMismatch),
+    }
+}
+
+// Inspects every byte of equally long inputs whatever their contents, like `CRYPTO_memcmp`.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && black_box(a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y))) == 0
+}
+
+/// Checks a prover's response against the verifier's tag.
+pub struct Verify<'a> {
+    tag: &'a Tag,
+}
+
+impl<'a> Verify<'a> {
+    pub fn new(tag: &'a Tag) -> Self {
+        Self { tag }
+    }
+
+    pub fn run(&self, response: &Response) -> Verdict {
+        verify(self.tag, response)
+    }
+}
+
+/// Public verification of a chain produced by [`Prove::run_with_proofs`](crate::Prove::run_with_proofs).
+///
+/// Every delay output is checked against its proof without any trapdoor, and every round response is passed to
+/// `check_round` together with its challenge, e.g. to spot-check it against a public Merkle commitment. The digests
+/// are recomputed with the expected `suite` and finally compared with the prover's `response`.
+pub fn verify_chain<D: DelayFunction + ?Sized>(
+    delay: &D,
+    c: &[u8],
+    k: usize,
+    suite: Suite,
+    response: &Response,
+    proof: &ChainProof,
+    mut check_round: impl FnMut(&[u8], &[u8]) -> Result<bool>,
+) -> Result<Verdict> {
+    if proof.rounds.len() != k + 1 {
+        return Ok(Verdict::Rejected(Rejection::Malformed));
+    }
+    let mut c = c.to_vec();
+    let mut cs = vec![];
+    let mut vs = vec![];
+    for (i, RoundProof { v, y, proof: pi }) in proof.rounds.iter().enumerate() {
+        if !check_round(&c, v)? {
+            return Ok(Verdict::Rejected(Rejection::RoundResponse { round: i }));
+        }
+        if !delay.verify(&suite.hash(Domain::DelayInput, v)?, y, pi)? {
+            return Ok(Verdict::Rejected(Rejection::DelayProof { round: i }));
+        }
+        cs.extend_from_slice(&c);
+        vs.extend_from_slice(v);
+        c = suite.hash(Domain::Challenge, y)?;
+    }
+    let digests = Tag {
+        cs: suite.hash(Domain::Transcript, &cs)?,
+        vs: suite.hash(Domain::Transcript, &vs)?,
+        suite,
+    };
+    Ok(verify(&digests, response))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "openssl")]
+    use crate::{
+        modulus::ModulusGenerator,
+        post::Prove,
+        round::{FileMac, Round},
+        rsa::RsaSquaring,
+        vdf::ProofKind,
+    };
+
+    // The chains carry delay proofs, which need OpenSSL.
+    #[cfg(feature = "openssl")]
+    const K: usize = 3;
+    #[cfg(feature = "openssl")]
+    const DATA: &[u8] = b"publicly verifiable data";
+
+    #[cfg(feature = "openssl")]
+    fn check_round(c: &[u8], v: &[u8]) -> Result<bool> {
+        Ok(FileMac(DATA).respond(Suite::default(), c)? == v)
+    }
+
+    #[cfg(feature = "openssl")]
+    fn chain(kind: ProofKind) {
+        let (modulus, _) = ModulusGenerator::new(1024).generate().unwrap();
+        let delay = RsaSquaring::new(&modulus, 100).unwrap().with_proofs(kind);
+        let (response, proof) = Prove::new(&delay, K)
+            .run_with_proofs(b"c", &FileMac(DATA))
+            .unwrap();
+        assert_eq!(
+            response,
+            Prove::new(&delay, K).run(b"c", &FileMac(DATA)).unwrap()
+        );
+        let verdict = |k, suite, proof: &ChainProof| {
+            verify_chain(&delay, b"c", k, suite, &response, proof, check_round).unwrap()
+        };
+        assert_eq!(verdict(K, Suite::default(), &proof), Verdict::Accepted);
+        assert_eq!(
+            verdict(K + 1, Suite::default(), &proof),
+            Verdict::Rejected(Rejection::Malformed)
+        );
+        assert!(!verdict(K, Suite::Sha3, &proof).is_accepted());
+
+        let mut forged = proof.clone();
+        *forged.rounds[1].y.last_mut().unwrap() ^= 1;
+        assert_eq!(
+            verdict(K, Suite::default(), &forged),
+            Verdict::Rejected(Rejection::DelayProof { round: 1 })
+        );
+        let mut forged = proof.clone();
+        forged.rounds[2].v[0] ^= 1;
+        assert_eq!(
+            verdict(K, Suite::default(), &forged),
+            Verdict::Rejected(Rejection::RoundResponse { round: 2 })
+        );
+        assert_eq!(
+            verify_chain(
+                &delay,
+                b"c",
+                K,
+                Suite::default(),
+                &response,
+                &forged,
+                |_, _| Ok(true)
+            )
+            .unwrap(),
+            Verdict::Rejected(Rejection::DelayProof { round: 2 })
+        );
+    }
+
+    #[test]
+    fn single_shot() {
+        let tag = Tag {
+            cs: vec![1; 32],
+            vs: vec![2; 32],
+            suite: Suite::default(),
+        };
+        assert_eq!(verify(&tag, &tag.clone()), Verdict::Accepted);
+        assert_eq!(Verify::new(&tag).run(&tag), Verdict::Accepted);
+
+        let short = Tag {
+            vs: vec![2; 31],
+            ..tag.clone()
+        };
+        assert_eq!(
+            verify(&tag, &short),
+            Verdict::Rejected(Rejection::Malformed)
+        );
+        assert!(matches!(
+            verify(&tag, &short).into_result(),
+            Err(PostError::MalformedProof(_))
+        ));
+        let foreign = Tag {
+            suite: Suite::Blake3,
+            ..tag.clone()
+        };
+        assert_eq!(
+            verify(&tag, &foreign),
+            Verdict::Rejected(Rejection::SuiteMismatch)
+        );
+        let mut wrong = tag.clone();
+        wrong.vs[5] ^= 1;
+        assert_eq!(
+            verify(&tag, &wrong),
+            Verdict::Rejected(Rejection::ResponseMismatch)
+        );
+        assert!(matches!(
+            verify(&tag, &wrong).into_result(),
+            Err(PostError::VerificationFailed)
+        ));
+        // A diverged chain is reported as such even if the responses differ too.
+        wrong.cs[0] ^= 1;
+        assert_eq!(
+            verify(&tag, &wrong),
+            Verdict::Rejected(Rejection::ChallengeMismatch)
+        );
+    }
+
+    #[cfg(feature = "openssl")]
+    #[test]
+    fn wesolowski_chain() {
+        chain(ProofKind::Wesolowski);
+    }
+
+    #[cfg(feature = "openssl")]
+    #[test]
+    fn pietrzak_chain() {
+        chain(ProofKind::Pietrzak);
+    }
+}
@@ -0,0 +1,170 @@
+#[cfg(feature = "openssl")]
+use ndss::vdf::ProofKind;
+use ndss::{
+    ChainProof, Encoding, FileMac, ModulusGenerator, Params, PostError, Prove, PublicParams,
+    RsaSquaring, Store, Suite, Tag, Trapdoor,
+};
+
+fn roundtrip<T: Encoding + PartialEq + std::fmt::Debug>(value: &T) {
+    assert_eq!(&T::from_binary(&value.to_binary().unwrap()).unwrap(), value);
+    assert_eq!(&T::from_json(&value.to_json().unwrap()).unwrap(), value);
+}
+
+fn keys() -> (PublicParams, Trapdoor) {
+    let (modulus, trapdoor) = ModulusGenerator::new(1024)
+        .no_small_order()
+        .generate()
+        .unwrap();
+    let params = Params::new(1024, 3, 1000)
+        .unwrap()
+        .with_suite(Suite::HmacSha256);
+    (PublicParams::new(modulus, params).unwrap(), trapdoor)
+}
+
+#[test]
+fn public_params() {
+    roundtrip(&keys().0);
+}
+
+#[test]
+fn trapdoor() {
+    let (public, trapdoor) = keys();
+    for decoded in [
+        Trapdoor::from_binary(&trapdoor.to_binary().unwrap()).unwrap(),
+        Trapdoor::from_json(&trapdoor.to_json().unwrap()).unwrap(),
+    ] {
+        assert_eq!(decoded.modulus().unwrap(), public.modulus);
+        assert!(decoded.no_small_order());
+    }
+}
+
+#[test]
+fn tag_and_response() {
+    let (public, trapdoor) = keys();
+    let c = [7; 32];
+    let file = b"file contents";
+    let k = public.params.k;
+    let squarings = public.params.squarings;
+    let suite = public.params.suite;
+
+    let tag = Store::new(RsaSquaring::with_trapdoor(&trapdoor, squarings).unwrap(), k)
+        .with_suite(suite)
+        .run(&c, &FileMac(file))
+        .unwrap();
+    roundtrip(&tag);
+
+    let response = Prove::new(RsaSquaring::new(&public.modulus, squarings).unwrap(), k)
+        .with_suite(suite)
+        .run(&c, &FileMac(file))
+        .unwrap();
+    assert_eq!(response, tag);
+    roundtrip(&response);
+}
+
+#[cfg(feature = "openssl")]
+#[test]
+fn chain_proof() {
+    let (public, _) = keys();
+    let delay = RsaSquaring::new(&public.modulus, public.params.squarings)
+        .unwrap()
+        .with_proofs(ProofKind::Pietrzak);
+    let (_, proof) = Prove::new(&delay, public.params.k)
+        .with_suite(public.params.suite)
+        .run_with_proofs(&[7; 32], &FileMac(b"file contents"))
+        .unwrap();
+    roundtrip(&proof);
+}
+
+#[test]
+fn rejects_other_types_and_versions() {
+    let tag = Tag {
+        cs: vec![1; 32],
+        vs: vec![2; 32],
+        suite: Suite::Blake3,
+    };
+    let bytes = tag.to_binary().unwrap();
+    assert!(matches!(
+        ChainProof::from_binary(&bytes),
+        Err(PostError::Encoding(_))
+    ));
+
+    let mut future = bytes.clone();
+    future[5] += 1;
+    assert!(matches!(
+        Tag::from_binary(&future),
+        Err(PostError::Encoding(_))
+    ));
+
+    let mut trailing = bytes.clone();
+    trailing.push(0);
+    assert!(matches!(
+        Tag::from_binary(&trailing),
+        Err(PostError::Encoding(_))
+    ));
+    assert!(Tag::from_binary(&bytes[..bytes.len() - 1]).is_err());
+
+    let json = tag
+        .to_json()
+        .unwrap()
+        .replace("\"version\": 1", "\"version\": 2");
+    assert!(matches!(Tag::from_json(&json), Err(PostError::Encoding(_))));
+
+    let short = Tag {
+        cs: vec![1; 31],
+        vs: vec![2; 32],
+        suite: Suite::Blake3,
+    };
+    assert!(Tag::from_json(&short.to_json().unwrap()).is_err());
+
+    let json = tag
+        .to_json()
+        .unwrap()
+        .replace("\"suite\": \"blake3\"", "\"suite\": \"md5\"");
+    assert!(matches!(Tag::from_json(&json), Err(PostError::Encoding(_))));
+}
+
+#[test]
+fn rejects_invalid_params() {
+    let (public, _) = keys();
+    let json = public
+        .to_json()
+        .unwrap()
+        .replace("\"n_bits\": 1024", "\"n_bits\": 2048");
+    assert!(matches!(
+        PublicParams::from_json(&json),
+        Err(PostError::InvalidParameters(_))
+    ));
+}
+
+#[test]
+fn transcript() {
+    let (public, trapdoor) = keys();
+    let k = public.params.k;
+    let squarings = public.params.squarings;
+    for outputs in [false, true] {
+        let (_, transcript) =
+            Store::new(RsaSquaring::with_trapdoor(&trapdoor, squarings).unwrap(), k)
+                .with_suite(public.params.suite)
+                .run_with_transcript(&[7; 32], &FileMac(b"file contents"), outputs)
+                .unwrap();
+        roundtrip(&transcript);
+    }
+}
+
+#[test]
+fn transcript_commitment() {
+    let (public, trapdoor) = keys();
+    let k = public.params.k;
+    let squarings = public.params.squarings;
+
+    let (_, expected) = Store::new(RsaSquaring::with_trapdoor(&trapdoor, squarings).unwrap(), k)
+        .with_suite(public.params.suite)
+        .run_with_transcript(&[7; 32], &FileMac(b"file contents"), false)
+        .unwrap();
+    let committed = expected.commit().unwrap();
+    roundtrip(&committed.commitment());
+    let openings = (0..=k)
+        .map(|i| committed.open(i).unwrap())
+        .collect::<Vec<_>>();
+    roundtrip(&openings);
+}
@@ -0,0 +1,149 @@
+use std::{
+    fs,
+    net::{SocketAddr, TcpListener},
+    path::PathBuf,
+    thread,
+    time::Duration,
+};
+
+use ndss::{
+    audit::{Finding, Message, Schedule},
+    net::{read_frame, write_frame, Audit, Hello, ProverDaemon, VerifierClient},
+    ModulusGenerator, Params, PublicParams, Rejection, Trapdoor, Verdict,
+};
+
+const DATA: &[u8] = b"the stored file, audited over the loopback interface";
+
+fn keys() -> (PublicParams, Trapdoor) {
+    let (modulus, trapdoor) = ModulusGenerator::new(1024).generate().unwrap();
+    let params = Params::new(1024, 6, 2000).unwrap();
+    (PublicParams::new(modulus, params).unwrap(), trapdoor)
+}
+
+fn hosted_file(name: &str, contents: &[u8]) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("ndss-{}-{}", std::process::id(), name));
+    fs::write(&path, contents).unwrap();
+    path
+}
+
+// Starts a prover daemon hosting `path` as "file" on an ephemeral localhost port.
+fn daemon(path: PathBuf, public: PublicParams) -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let mut daemon = ProverDaemon::new();
+    daemon.host("file", path, public);
+    thread::spawn(move || daemon.serve(listener));
+    addr
+}
+
+fn schedule(round_time: Duration) -> Schedule {
+    Schedule::new(2, round_time, Duration::from_secs(5)).unwrap()
+}
+
+#[test]
+fn lifecycle() {
+    let (public, trapdoor) = keys();
+    let audits = (0..2)
+        .map(|_| Audit::prepare("file", DATA, &public, &trapdoor).unwrap())
+        .collect::<Vec<_>>();
+    let addr = daemon(hosted_file("lifecycle", DATA), public);
+
+    let mut client = VerifierClient::new(addr, schedule(Duration::from_secs(1)), Duration::ZERO);
+    client.run(audits, Duration::ZERO).unwrap();
+    assert_eq!(client.records().len(), 2);
+    for record in client.records() {
+        assert!(record.passed(), "{:?}", record);
+    }
+}
+
+#[test]
+fn corrupted_file() {
+    let (public, trapdoor) = keys();
+    let audit = Audit::prepare("file", DATA, &public, &trapdoor).unwrap();
+    let addr = daemon(hosted_file("corrupted", b"some other file"), public);
+
+    let mut client = VerifierClient::new(addr, schedule(Duration::from_secs(1)), Duration::ZERO);
+    let record = client.audit(audit).unwrap();
+    assert_eq!(
+        record.verdict,
+        Some(Verdict::Rejected(Rejection::ChallengeMismatch))
+    );
+    assert_eq!(
+        record.findings[0],
+        Finding::Wrong {
+            round: 0,
+            rejection: Rejection::RoundResponse { round: 0 }
+        }
+    );
+}
+
+#[test]
+fn late_checkpoints() {
+    let (public, trapdoor) = keys();
+    let audit = Audit::prepare("file", DATA, &public, &trapdoor).unwrap();
+    let addr = daemon(hosted_file("late", DATA), public);
+
+    // No time at all for the delays or the network: every checkpoint is late.
+    let schedule = Schedule::new(2, Duration::ZERO, Duration::ZERO).unwrap();
+    let mut client = VerifierClient::new(addr, schedule, Duration::from_secs(60));
+    let record = client.audit(audit).unwrap();
+    assert_eq!(record.verdict, Some(Verdict::Accepted));
+    assert!(!record.findings.is_empty());
+    assert!(record.findings.iter().all(|f| matches!(
+        f,
+        Finding::Late { .. } | Finding::LateResponse { .. } | Finding::Missing { .. }
+    )));
+    assert!(matches!(
+        record.findings.last(),
+        Some(Finding::LateResponse { .. })
+    ));
+}
+
+#[test]
+fn unknown_file() {
+    let (public, trapdoor) = keys();
+    let audit = Audit {
+        file: "elsewhere".into(),
+        ..Audit::prepare("file", DATA, &public, &trapdoor).unwrap()
+    };
+    let addr = daemon(hosted_file("unknown", DATA), public);
+
+    let mut client = VerifierClient::new(addr, schedule(Duration::from_secs(1)), Duration::ZERO);
+    let record = client.audit(audit).unwrap();
+    assert_eq!(record.verdict, None);
+    assert_eq!(
+        record.findings,
+        [0, 2, 4, 6].map(|round| Finding::Missing { round })
+    );
+}
+
+#[test]
+fn rogue_prover() {
+    let (public, trapdoor) = keys();
+    let audits = (0..2)
+        .map(|_| Audit::prepare("file", DATA, &public, &trapdoor).unwrap())
+        .collect::<Vec<_>>();
+    // Answers every challenge with a challenge of its own.
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let _: Hello = read_frame(&mut stream).unwrap();
+            let challenge: Message = read_frame(&mut stream).unwrap();
+            write_frame(&mut stream, &challenge).unwrap();
+        }
+    });
+
+    let mut client = VerifierClient::new(addr, schedule(Duration::from_secs(1)), Duration::ZERO);
+    client.run(audits, Duration::ZERO).unwrap();
+    assert_eq!(client.records().len(), 2);
+    for record in client.records() {
+        assert_eq!(record.verdict, None);
+        assert!(matches!(
+            record.findings.last(),
+            Some(Finding::Violation { .. })
+        ));
+        assert!(!record.passed());
+    }
+}
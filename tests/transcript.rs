@@ -0,0 +1,94 @@
+use ndss::{
+    transcript::{sample_rounds, spot_check, RoundOpening},
+    FileMac, ModulusGenerator, Params, Prove, PublicParams, Rejection, RsaSquaring, Store,
+    Trapdoor, Verdict,
+};
+
+const C: [u8; 32] = [7; 32];
+
+fn keys() -> (PublicParams, Trapdoor) {
+    let (modulus, trapdoor) = ModulusGenerator::new(1024).generate().unwrap();
+    let params = Params::new(1024, 3, 1000).unwrap();
+    (PublicParams::new(modulus, params).unwrap(), trapdoor)
+}
+
+#[test]
+fn first_divergence() {
+    let (public, trapdoor) = keys();
+    let file = b"file contents";
+    let k = public.params.k;
+    let squarings = public.params.squarings;
+
+    let (tag, expected) = Store::new(RsaSquaring::with_trapdoor(&trapdoor, squarings).unwrap(), k)
+        .run_with_transcript(&C, &FileMac(file), true)
+        .unwrap();
+    let (response, actual) = Prove::new(RsaSquaring::new(&public.modulus, squarings).unwrap(), k)
+        .run_with_transcript(&C, &FileMac(file), false)
+        .unwrap();
+    assert_eq!(response, tag);
+    assert_eq!(expected.tag().unwrap(), tag);
+    assert_eq!(expected.rounds.len(), k + 1);
+    assert_eq!(expected.first_divergence(&actual), None);
+
+    let mut diverged = actual.clone();
+    for round in &mut diverged.rounds[2..] {
+        round.v[0] ^= 1;
+    }
+    assert_eq!(expected.first_divergence(&diverged), Some(2));
+    diverged.rounds.truncate(1);
+    assert_eq!(expected.first_divergence(&diverged), Some(1));
+}
+
+#[test]
+fn spot_checks() {
+    let (public, trapdoor) = keys();
+    let k = public.params.k;
+    let squarings = public.params.squarings;
+
+    let (_, expected) = Store::new(RsaSquaring::with_trapdoor(&trapdoor, squarings).unwrap(), k)
+        .run_with_transcript(&C, &FileMac(b"file contents"), false)
+        .unwrap();
+    let committed = expected.clone().commit().unwrap();
+    let commitment = committed.commitment();
+
+    let rounds = sample_rounds(commitment.rounds, 2).unwrap();
+    let openings = rounds
+        .iter()
+        .map(|&i| committed.open(i).unwrap())
+        .collect::<Vec<_>>();
+    assert!(spot_check(&expected, &commitment, &rounds, &openings)
+        .unwrap()
+        .is_accepted());
+
+    let (_, other) = Prove::new(RsaSquaring::new(&public.modulus, squarings).unwrap(), k)
+        .run_with_transcript(&C, &FileMac(b"other contents"), false)
+        .unwrap();
+    let other = other.commit().unwrap();
+    let forged = [other.open(0).unwrap()];
+    assert!(!spot_check(&expected, &other.commitment(), &[0], &forged)
+        .unwrap()
+        .is_accepted());
+    // The openings must be exactly the requested ones: neither none at all, nor rounds of the prover's choosing.
+    let malformed = Verdict::Rejected(Rejection::Malformed);
+    assert_eq!(
+        spot_check(&expected, &other.commitment(), &rounds, &[]).unwrap(),
+        malformed
+    );
+    let substituted = rounds
+        .iter()
+        .map(|&i| committed.open((i + 1) % commitment.rounds).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        spot_check(&expected, &commitment, &rounds, &substituted).unwrap(),
+        malformed
+    );
+    assert_eq!(
+        spot_check(&expected, &commitment, &rounds, &openings[..1]).unwrap(),
+        malformed
+    );
+    let moved = RoundOpening {
+        round: 1,
+        ..committed.open(0).unwrap()
+    };
+    assert!(!commitment.check(&moved).unwrap());
+}